@@ -1,14 +1,16 @@
-use std::fs::File;
-use std::io::{self, BufWriter};
-use std::str::FromStr;
+use std::error::Error;
 
-use weekly_planner::weekplan::{Time, Weekday};
-use weekly_planner::{poli_plan, WeekPlan};
+use weekly_planner::checked_poli_plan;
+use weekly_planner::weekplan::format;
 
-fn main() -> Result<(), io::Error> {
-    let start = Time::new(8, 30).unwrap();
-    let week_plan = poli_plan! {
-        start, 90, 7,
+/// Writes the sample plan to the path given as argument, `data/plan.json` by
+/// default, in the format matching its extension.
+fn main() -> Result<(), Box<dyn Error>> {
+    let path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "data/plan.json".to_owned());
+    let week_plan = checked_poli_plan! {
+        "8:30", 90, 7,
         "Monday" => "10:00", 2, "Computer architectures",
         "Tuesday" => "8:30", 2, "Computer architectures",
         "Tuesday" => "11:30", 2, "Electronics",
@@ -21,11 +23,9 @@ fn main() -> Result<(), io::Error> {
         "Friday" => "14:30", 1, "Simulation",
     };
 
-    println!("Writing to data/plan.json");
+    println!("Writing to {path}");
 
-    let outfile = File::create("data/plan.json")?;
-    let writer = BufWriter::new(outfile);
-    serde_json::to_writer_pretty(writer, &week_plan)?;
+    format::save(&path, &week_plan)?;
 
     Ok(())
 }
@@ -0,0 +1,51 @@
+//! The rules of the slot grid of a plan, in minutes since midnight.
+
+use crate::time::MINUTES_PER_DAY;
+
+/// Whether `slots` slots of `slot_duration` minutes from `start` make a plan:
+/// slots last between a minute and a day, and the last one ends by midnight
+/// of the next day.
+pub fn fits(start: u16, slot_duration: u16, slots: u8) -> bool {
+    (1..=MINUTES_PER_DAY).contains(&slot_duration)
+        && u16::from(slots)
+            .checked_mul(slot_duration)
+            .and_then(|minutes| start.checked_add(minutes))
+            .is_some_and(|end| end <= MINUTES_PER_DAY)
+}
+
+/// Whether `time` is the start of one of the slots of a grid that
+/// [`fits`].
+pub fn is_slot_start(start: u16, slot_duration: u16, slots: u8, time: u16) -> bool {
+    let Some(distance) = time.checked_sub(start) else {
+        return false;
+    };
+    let Ok(slot_index) = u8::try_from(distance / slot_duration) else {
+        return false;
+    };
+
+    distance % slot_duration == 0 && slot_index < slots
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn edges() {
+        let time = |hour: u16, minute: u16| hour * 60 + minute;
+
+        assert!(!fits(time(8, 30), 0, 7));
+        assert!(!fits(time(8, 30), 0, 0));
+        assert!(fits(time(8, 30), 90, 0));
+        assert!(fits(time(21, 0), 90, 2));
+        assert!(!fits(time(21, 0), 90, 3));
+        assert!(fits(time(0, 0), 1440, 1));
+        assert!(!fits(time(0, 0), 1441, 0));
+        assert!(!fits(time(24, 0), 1, 1));
+
+        assert!(is_slot_start(time(21, 0), 90, 2, time(22, 30)));
+        assert!(!is_slot_start(time(21, 0), 90, 2, MINUTES_PER_DAY));
+        assert!(!is_slot_start(time(8, 30), 90, 0, time(8, 30)));
+        assert!(!is_slot_start(time(8, 30), 90, 7, time(8, 0)));
+    }
+}
@@ -0,0 +1,10 @@
+//! The rules `weekly_planner` and its macros share: how times and weekdays
+//! are parsed and which slot grids make a plan, so that plans are checked the
+//! same way at compile time as at run time.
+//!
+//! Times are counted in minutes since midnight and weekdays in days since
+//! Monday; `weekly_planner` wraps them in its `Time` and `Weekday` types.
+
+pub mod grid;
+pub mod time;
+pub mod weekday;
@@ -0,0 +1,252 @@
+//! Times of day, from `00:00` to `24:00`.
+
+use std::fmt;
+
+/// Minutes in a day, from midnight to midnight of the next day.
+pub const MINUTES_PER_DAY: u16 = 24 * 60;
+
+/// The reason a string could not be parsed as a time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseTimeErrorKind {
+    Empty,
+    BadFormat,
+    InvalidHour,
+    InvalidMinute,
+    InvalidSecond,
+    BreaksInvariant,
+}
+
+impl fmt::Display for ParseTimeErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            ParseTimeErrorKind::Empty => "empty time",
+            ParseTimeErrorKind::BadFormat => "bad format",
+            ParseTimeErrorKind::InvalidHour => "invalid hour",
+            ParseTimeErrorKind::InvalidMinute => "invalid minute",
+            ParseTimeErrorKind::InvalidSecond => "invalid second, only :00 is supported",
+            ParseTimeErrorKind::BreaksInvariant => "time doesn't respect the invariants for Time",
+        };
+
+        write!(f, "{msg}")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseTimeError {
+    input: String,
+    position: usize,
+    kind: ParseTimeErrorKind,
+}
+
+impl ParseTimeError {
+    pub fn kind(&self) -> ParseTimeErrorKind {
+        self.kind
+    }
+
+    /// The string that failed to parse.
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    /// Byte offset into [`ParseTimeError::input`] where the problem was found.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+impl fmt::Display for ParseTimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} in {:?} at position {}",
+            self.kind, self.input, self.position
+        )
+    }
+}
+
+impl std::error::Error for ParseTimeError {}
+
+/// A cursor over the input being parsed. Positions are byte offsets into the
+/// original, untrimmed string so that errors can point at the culprit.
+struct Cursor<'a> {
+    input: &'a str,
+    pos: usize,
+    end: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..self.end].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn eat(&mut self, accepted: &[char]) -> Option<char> {
+        self.peek().filter(|c| accepted.contains(c))?;
+        self.bump()
+    }
+
+    fn digits(&mut self) -> &'a str {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.bump();
+        }
+        &self.input[start..self.pos]
+    }
+
+    fn error(&self, kind: ParseTimeErrorKind, position: usize) -> ParseTimeError {
+        ParseTimeError {
+            input: self.input.to_owned(),
+            position,
+            kind,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Meridiem {
+    Am,
+    Pm,
+}
+
+/// Strips a trailing `am`/`pm` marker (case-insensitive, optionally dotted
+/// as `a.m.`) and returns where the marker starts.
+fn strip_meridiem(input: &str, end: usize) -> (usize, Option<Meridiem>) {
+    let lower = input[..end].to_ascii_lowercase();
+
+    for (suffix, meridiem) in [
+        ("a.m.", Meridiem::Am),
+        ("p.m.", Meridiem::Pm),
+        ("am", Meridiem::Am),
+        ("pm", Meridiem::Pm),
+    ] {
+        if let Some(rest) = lower.strip_suffix(suffix) {
+            return (rest.trim_end().len(), Some(meridiem));
+        }
+    }
+
+    (end, None)
+}
+
+/// Parses a time of day into its hour and minute, up to `24:00`:
+///
+/// - `8:30`, `08:30`, `8.30`, `8h30`, `8h`
+/// - `0830`, `830`
+/// - `8:30am`, `8:30 PM`, `8am`, `12 p.m.`
+/// - ISO-8601 `T08:30`, `T08:30:00`, `08:30:00`
+pub fn parse(input: &str) -> Result<(u8, u8), ParseTimeError> {
+    let start = input.len() - input.trim_start().len();
+    let end = input.trim_end().len();
+
+    let (end, meridiem) = strip_meridiem(input, end);
+    let mut cursor = Cursor {
+        input,
+        pos: start,
+        end,
+    };
+
+    if cursor.peek().is_none() {
+        return Err(cursor.error(ParseTimeErrorKind::Empty, start));
+    }
+
+    let iso = cursor.eat(&['T', 't']).is_some();
+
+    let hour_pos = cursor.pos;
+    let hour_digits = cursor.digits();
+    if hour_digits.is_empty() {
+        return Err(cursor.error(ParseTimeErrorKind::BadFormat, hour_pos));
+    }
+
+    let separator = if iso {
+        cursor.eat(&[':'])
+    } else {
+        cursor.eat(&[':', '.', 'h', 'H'])
+    };
+
+    let (hour, minute, minute_pos) = match separator {
+        Some(separator) => {
+            let minute_pos = cursor.pos;
+            let minute_digits = cursor.digits();
+            let minute = match (separator, minute_digits.len()) {
+                ('h' | 'H', 0) => "0",
+                (_, 2) => minute_digits,
+                _ => return Err(cursor.error(ParseTimeErrorKind::InvalidMinute, minute_pos)),
+            };
+
+            if separator == ':' && cursor.eat(&[':']).is_some() {
+                let second_pos = cursor.pos;
+                let second = cursor.digits();
+                if second.len() != 2 || second != "00" {
+                    return Err(cursor.error(ParseTimeErrorKind::InvalidSecond, second_pos));
+                }
+            }
+
+            (hour_digits, minute, minute_pos)
+        }
+        None => match hour_digits.len() {
+            1 | 2 if meridiem.is_some() => (hour_digits, "0", cursor.pos),
+            3 | 4 => {
+                let split = hour_digits.len() - 2;
+                (
+                    &hour_digits[..split],
+                    &hour_digits[split..],
+                    hour_pos + split,
+                )
+            }
+            _ => return Err(cursor.error(ParseTimeErrorKind::BadFormat, cursor.pos)),
+        },
+    };
+
+    if cursor.peek().is_some() {
+        return Err(cursor.error(ParseTimeErrorKind::BadFormat, cursor.pos));
+    }
+
+    let hour: u8 = hour
+        .parse()
+        .map_err(|_| cursor.error(ParseTimeErrorKind::InvalidHour, hour_pos))?;
+    let minute: u8 = minute
+        .parse()
+        .map_err(|_| cursor.error(ParseTimeErrorKind::InvalidMinute, minute_pos))?;
+
+    let hour = match meridiem {
+        None => hour,
+        Some(_) if !(1..=12).contains(&hour) => {
+            return Err(cursor.error(ParseTimeErrorKind::InvalidHour, hour_pos));
+        }
+        Some(Meridiem::Am) => hour % 12,
+        Some(Meridiem::Pm) => hour % 12 + 12,
+    };
+
+    if hour > 24 {
+        return Err(cursor.error(ParseTimeErrorKind::InvalidHour, hour_pos));
+    }
+    if minute >= 60 {
+        return Err(cursor.error(ParseTimeErrorKind::InvalidMinute, minute_pos));
+    }
+
+    if hour == 24 && minute != 0 {
+        return Err(cursor.error(ParseTimeErrorKind::BreaksInvariant, start));
+    }
+
+    Ok((hour, minute))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn hour_and_minute() {
+        assert_eq!(parse("8:30 pm"), Ok((20, 30)));
+        assert_eq!(parse("T00:00:00"), Ok((0, 0)));
+        assert_eq!(parse("24:00"), Ok((24, 0)));
+        assert_eq!(
+            parse("24:30").unwrap_err().kind(),
+            ParseTimeErrorKind::BreaksInvariant
+        );
+    }
+}
@@ -0,0 +1,114 @@
+//! Days of the week, counted from Monday as in ISO-8601.
+
+use std::fmt;
+
+/// The names of the days from Monday, in English.
+pub const ENGLISH: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+/// The names of the days from Monday, in Italian.
+pub const ITALIAN: [&str; 7] = [
+    "Lunedì",
+    "Martedì",
+    "Mercoledì",
+    "Giovedì",
+    "Venerdì",
+    "Sabato",
+    "Domenica",
+];
+
+/// The names [`parse`] accepts, in every built-in language.
+const LANGUAGES: [[&str; 7]; 2] = [ENGLISH, ITALIAN];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseWeekdayError {
+    input: String,
+}
+
+impl ParseWeekdayError {
+    /// The string that failed to parse.
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+}
+
+impl fmt::Display for ParseWeekdayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid weekday {:?}", self.input)
+    }
+}
+
+impl std::error::Error for ParseWeekdayError {}
+
+/// Lowercases and strips the accents used by the built-in languages.
+fn fold(s: &str) -> String {
+    s.trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'à' => 'a',
+            'è' | 'é' => 'e',
+            'ì' => 'i',
+            'ò' => 'o',
+            'ù' => 'u',
+            c => c,
+        })
+        .collect()
+}
+
+/// Parses a weekday into the number of days since Monday, accepting,
+/// ignoring case and accents:
+///
+/// - full names in any built-in language (`Monday`, `lunedì`, `Lunedi`)
+/// - prefixes of at least three letters that identify a single day
+///   (`Mon`, `Tues`, `Thurs`, `Mer`)
+/// - ISO-8601 numbers, from `1` for Monday to `7` for Sunday
+pub fn parse(s: &str) -> Result<usize, ParseWeekdayError> {
+    let err = || ParseWeekdayError {
+        input: s.to_owned(),
+    };
+    let input = fold(s);
+
+    if let Ok(number) = input.parse::<usize>() {
+        return (1..=7)
+            .contains(&number)
+            .then(|| number - 1)
+            .ok_or_else(err);
+    }
+
+    if input.chars().count() < 3 {
+        return Err(err());
+    }
+
+    let mut matches = (0..7).filter(|&day| {
+        LANGUAGES
+            .iter()
+            .any(|names| fold(names[day]).starts_with(&input))
+    });
+
+    match (matches.next(), matches.next()) {
+        (Some(day), None) => Ok(day),
+        _ => Err(err()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn days_since_monday() {
+        assert_eq!(parse("Monday"), Ok(0));
+        assert_eq!(parse("domenica"), Ok(6));
+        assert_eq!(parse(" 7 "), Ok(6));
+        assert_eq!(parse("0").unwrap_err().input(), "0");
+        assert_eq!(parse("T").unwrap_err().input(), "T");
+    }
+}
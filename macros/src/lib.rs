@@ -0,0 +1,314 @@
+//! Procedural macros of `weekly_planner`, which re-exports them.
+
+use std::collections::HashMap;
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::{LitInt, LitStr, Token};
+
+use weekly_planner_core::{grid, time, weekday};
+
+/// Builds a `WeekPlan` like `poli_plan!`, checking the plan while compiling:
+/// weekdays, times, slots and overlapping bookings are reported as compile
+/// errors pointing at the offending literal.
+///
+/// Every value must be a literal: the start time, slot duration and number of
+/// slots, then bookings as `"weekday" => "time", slots, "activity"`.
+#[proc_macro]
+pub fn checked_poli_plan(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    match expand(input.into()) {
+        Ok(expanded) => expanded.into(),
+        Err(err) => {
+            // A block, as several `compile_error!`s in a row aren't an
+            // expression.
+            let errors = err.into_compile_error();
+            quote!({ #errors }).into()
+        }
+    }
+}
+
+struct Booking {
+    weekday: LitStr,
+    time: LitStr,
+    len: LitInt,
+}
+
+impl Parse for Booking {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let weekday = input.parse()?;
+        input.parse::<Token![=>]>()?;
+        let time = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let len = input.parse()?;
+        input.parse::<Token![,]>()?;
+        input.parse::<LitStr>()?;
+
+        Ok(Booking { weekday, time, len })
+    }
+}
+
+struct Plan {
+    start: LitStr,
+    slot_duration: LitInt,
+    slots: LitInt,
+    bookings: Vec<Booking>,
+}
+
+impl Parse for Plan {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let start = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let slot_duration = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let slots = input.parse()?;
+
+        let mut bookings = Vec::new();
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            bookings.push(input.parse()?);
+        }
+
+        Ok(Plan {
+            start,
+            slot_duration,
+            slots,
+            bookings,
+        })
+    }
+}
+
+/// The layout of a plan whose literals are all valid, in minutes since
+/// midnight.
+struct Layout {
+    start: u16,
+    slot_duration: u16,
+    slots: u8,
+}
+
+impl Layout {
+    fn new(start: u16, slot_duration: u16, slots: u8) -> Option<Self> {
+        grid::fits(start, slot_duration, slots).then_some(Layout {
+            start,
+            slot_duration,
+            slots,
+        })
+    }
+
+    fn is_valid_slot(&self, slot: u16) -> bool {
+        grid::is_slot_start(self.start, self.slot_duration, self.slots, slot)
+    }
+}
+
+/// Collects errors so that every problem is reported at once.
+#[derive(Default)]
+struct Errors(Option<syn::Error>);
+
+impl Errors {
+    fn push(&mut self, err: syn::Error) {
+        match &mut self.0 {
+            Some(errors) => errors.combine(err),
+            None => self.0 = Some(err),
+        }
+    }
+
+    fn check<T>(&mut self, result: syn::Result<T>) -> Option<T> {
+        result.map_err(|err| self.push(err)).ok()
+    }
+}
+
+fn parse_str<T, E>(lit: &LitStr, parse: impl Fn(&str) -> Result<T, E>) -> syn::Result<T>
+where
+    E: std::fmt::Display,
+{
+    parse(&lit.value()).map_err(|err| syn::Error::new(lit.span(), err))
+}
+
+/// Minutes since midnight of a time literal.
+fn parse_time(lit: &LitStr) -> syn::Result<u16> {
+    parse_str(lit, time::parse).map(|(hour, minute)| u16::from(hour) * 60 + u16::from(minute))
+}
+
+/// Formats minutes since midnight as `Time` does, `HH:MM`.
+fn clock(minutes: u16) -> String {
+    format!("{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
+fn expand(input: TokenStream) -> syn::Result<TokenStream> {
+    let plan: Plan = syn::parse2(input.clone())?;
+    let mut errors = Errors::default();
+
+    let start = errors.check(parse_time(&plan.start));
+    let slot_duration = errors.check(plan.slot_duration.base10_parse::<u16>());
+    let slots = errors.check(plan.slots.base10_parse::<u8>());
+
+    let layout = match (start, slot_duration, slots) {
+        (Some(start), Some(slot_duration), Some(slots)) => {
+            let layout = Layout::new(start, slot_duration, slots);
+            if layout.is_none() {
+                errors.push(syn::Error::new(
+                    plan.slot_duration.span(),
+                    "slots must last between a minute and a day and end by midnight",
+                ));
+            }
+            layout
+        }
+        _ => None,
+    };
+
+    let mut booked: HashMap<(usize, u16), &LitStr> = HashMap::new();
+    for booking in &plan.bookings {
+        let day = errors.check(parse_str(&booking.weekday, weekday::parse));
+        let time = errors.check(parse_time(&booking.time));
+        let len = errors.check(booking.len.base10_parse::<u8>());
+        let (Some(layout), Some(day), Some(time), Some(len)) = (&layout, day, time, len) else {
+            continue;
+        };
+
+        if let Err(err) = book(layout, &mut booked, booking, day, time, len) {
+            errors.push(err);
+        }
+    }
+
+    match errors.0 {
+        Some(err) => Err(err),
+        None => Ok(quote! {
+            ::weekly_planner::poli_plan! { #input }
+        }),
+    }
+}
+
+/// Checks a booking of `len` slots from `time` on the `day`-th day from
+/// Monday and marks its slots as booked, booking none of them if one can't be
+/// booked, as `Builder::book` does.
+fn book<'a>(
+    layout: &Layout,
+    booked: &mut HashMap<(usize, u16), &'a LitStr>,
+    booking: &'a Booking,
+    day: usize,
+    time: u16,
+    len: u8,
+) -> syn::Result<()> {
+    let mut slots = Vec::with_capacity(usize::from(len));
+    for i in 0..len {
+        let slot = u16::from(i)
+            .checked_mul(layout.slot_duration)
+            .and_then(|minutes| time.checked_add(minutes))
+            .filter(|&slot| layout.is_valid_slot(slot));
+        let Some(slot) = slot else {
+            return Err(if i == 0 {
+                syn::Error::new(booking.time.span(), format!("Invalid slot {}", clock(time)))
+            } else {
+                syn::Error::new(
+                    booking.len.span(),
+                    "Slot is outside of the last hour for the day",
+                )
+            });
+        };
+
+        if let Some(first) = booked.get(&(day, slot)) {
+            let mut err = syn::Error::new(
+                booking.time.span(),
+                format!(
+                    "Slot {} {} already booked",
+                    weekday::ENGLISH[day],
+                    clock(slot)
+                ),
+            );
+            err.combine(syn::Error::new(first.span(), "first booked here"));
+            return Err(err);
+        }
+        slots.push(slot);
+    }
+
+    for slot in slots {
+        booked.insert((day, slot), &booking.time);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn problems(input: TokenStream) -> Vec<String> {
+        match expand(input) {
+            Ok(_) => Vec::new(),
+            Err(err) => err.into_iter().map(|err| err.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn valid() {
+        let expanded = expand(quote! {
+            "8:30", 90, 7,
+            "Monday" => "10:00", 2, "Computer architectures",
+            "mar" => "0830", 2, "Electronics",
+            "Monday" => "13:00", 1, "Electronics",
+        })
+        .unwrap();
+
+        assert!(expanded
+            .to_string()
+            .starts_with(":: weekly_planner :: poli_plan !"));
+        assert!(problems(quote! { "8:30", 90, 7 }).is_empty());
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(
+            problems(quote! {
+                "8:30", 90, 4,
+                "Moonday" => "10:00", 1, "A",
+                "Monday" => "25:00", 1, "B",
+                "Monday" => "10:15", 1, "C",
+                "Friday" => "13:00", 2, "D",
+                "Tuesday" => "8:30", 2, "E",
+                "Tuesday" => "10:00", 1, "F",
+            }),
+            [
+                "invalid weekday \"Moonday\"",
+                "invalid hour in \"25:00\" at position 0",
+                "Invalid slot 10:15",
+                "Slot is outside of the last hour for the day",
+                "Slot Tuesday 10:00 already booked",
+                "first booked here",
+            ]
+        );
+    }
+
+    #[test]
+    fn layout() {
+        assert_eq!(
+            problems(quote! { "8:3O", 90, 300, "Monday" => "8:30", 1, "A" }),
+            [
+                "invalid minute in \"8:3O\" at position 2",
+                "number too large to fit in target type",
+            ]
+        );
+        assert_eq!(
+            problems(quote! { "23:00", 90, 2, "Monday" => "8:30", 1, "A" }),
+            ["slots must last between a minute and a day and end by midnight"]
+        );
+        assert_eq!(
+            problems(quote! { "8:30", 90, 7, "Monday" => 830, 1, "A" }),
+            ["expected string literal"]
+        );
+
+        // The edges of `WeekPlan::new`
+        assert_eq!(
+            problems(quote! { "8:30", 0, 7 }),
+            ["slots must last between a minute and a day and end by midnight"]
+        );
+        assert!(problems(quote! { "8:30", 90, 0 }).is_empty());
+        assert_eq!(
+            problems(quote! { "8:30", 90, 0, "Monday" => "8:30", 1, "A" }),
+            ["Invalid slot 08:30"]
+        );
+        assert!(problems(quote! { "21:00", 90, 2, "Sunday" => "21:00", 2, "A" }).is_empty());
+    }
+}
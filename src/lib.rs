@@ -1,3 +1,7 @@
+// Lets `checked_poli_plan!` refer to the crate by name from inside it too.
+extern crate self as weekly_planner;
+
 pub mod weekplan;
 
+pub use weekly_planner_macros::checked_poli_plan;
 pub use weekplan::WeekPlan;
@@ -1,16 +1,447 @@
+use std::error::Error;
 use std::fs::File;
-use std::io::{self, BufReader, Write};
+use std::io::{BufReader, BufWriter, IsTerminal, Write};
+use std::path::Path;
+use std::process::ExitCode;
+use std::thread;
+use std::time::{Duration, SystemTime};
 
+use weekly_planner::weekplan::csv::Csv;
+use weekly_planner::weekplan::format::{self, Format};
+use weekly_planner::weekplan::locale::{Language, Locale};
+use weekly_planner::weekplan::render::{
+    AsciiDoc, Html, ICalendar, Layout, Markdown, PageSize, Pdf, Render, Svg, Terminal, Theme,
+};
+use weekly_planner::weekplan::{migration, schema, validation};
 use weekly_planner::WeekPlan;
 
-fn main() -> Result<(), io::Error> {
-    let week_plan: WeekPlan = {
-        let infile = File::open("data/plan.json")?;
-        let reader = BufReader::new(infile);
-        serde_json::from_reader(reader)?
+mod server;
+mod stty;
+mod tui;
+
+const PLAN_PATH: &str = "data/plan.json";
+const OUTPUT_PATH: &str = "output/week_plan.html";
+
+/// How often `watch` looks for changes.
+const WATCH_INTERVAL: Duration = Duration::from_millis(300);
+
+const USAGE: &str = "usage: main [COMMAND] [PLAN] [OPTIONS]
+
+commands: render (default), show, edit, check, migrate, import, schema, serve, watch
+
+options:
+    -o, --output FILE       .html, .pdf, .svg, .md, .adoc, .csv, .tsv or .ics
+    --lang CODE             built-in translation
+    --locale FILE           translation file
+    --theme NAME            light, dark, high-contrast or print
+    --css FILE              stylesheet replacing the theme
+    --template FILE         HTML page template
+    --page-size SIZE        a4 or letter
+    --calendar-domain NAME  domain of the event UIDs in .ics files
+    --editor                make the HTML timetable editable in the browser
+    --agenda                list bookings by day in Markdown and AsciiDoc
+    --grid                  export the timetable layout in CSV and TSV
+    --width COLUMNS         terminal width for `show`
+    --no-color              plain `show` and `edit` output
+    --from FILE             bookings spreadsheet for `import`
+    --port PORT             port for `serve`, 8080 by default
+    --live-reload           reload the pages of `serve` when the plan changes,
+                            and serve them while running `watch`";
+
+/// Command line arguments: a command, an optional plan path and options.
+#[derive(Default, Clone)]
+struct Args {
+    command: Option<String>,
+    path: Option<String>,
+    output: Option<String>,
+    locale: Locale,
+    theme: Theme,
+    css: Option<String>,
+    template: Option<String>,
+    page_size: PageSize,
+    calendar_domain: Option<String>,
+    editor: bool,
+    width: Option<usize>,
+    no_color: bool,
+    layout: Layout,
+    grid: bool,
+    from: Option<String>,
+    port: Option<u16>,
+    live_reload: bool,
+    help: bool,
+    /// The files given to `--locale`, `--css` and `--template`, read into
+    /// the fields above.
+    locale_file: Option<String>,
+    css_file: Option<String>,
+    template_file: Option<String>,
+}
+
+impl Args {
+    fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, Box<dyn Error>> {
+        let mut parsed = Args::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| format!("missing value for `{arg}`"))
+            };
+
+            match arg.as_str() {
+                "--lang" => {
+                    parsed.locale = value()?.parse::<Language>()?.into();
+                    parsed.locale_file = None;
+                }
+                "--locale" => parsed.locale_file = Some(value()?),
+                "-o" | "--output" => parsed.output = Some(value()?),
+                "--page-size" => parsed.page_size = value()?.parse()?,
+                "--calendar-domain" => parsed.calendar_domain = Some(value()?),
+                "--width" => parsed.width = Some(value()?.parse()?),
+                "--editor" => parsed.editor = true,
+                "--no-color" => parsed.no_color = true,
+                "--agenda" => parsed.layout = Layout::Agenda,
+                "--grid" => parsed.grid = true,
+                "--from" => parsed.from = Some(value()?),
+                "--port" => parsed.port = Some(value()?.parse()?),
+                "--live-reload" => parsed.live_reload = true,
+                "--theme" => parsed.theme = value()?.parse()?,
+                "--css" => parsed.css_file = Some(value()?),
+                "--template" => parsed.template_file = Some(value()?),
+                "-h" | "--help" => {
+                    parsed.help = true;
+                    return Ok(parsed);
+                }
+                option if option.starts_with('-') => {
+                    return Err(format!("unknown option `{option}`\n{USAGE}").into());
+                }
+                _ if parsed.command.is_none() => parsed.command = Some(arg),
+                _ if parsed.path.is_none() => parsed.path = Some(arg),
+                _ => return Err(format!("unexpected argument `{arg}`\n{USAGE}").into()),
+            }
+        }
+
+        parsed.read_inputs()?;
+        Ok(parsed)
+    }
+
+    /// Reads the files given to options.
+    fn read_inputs(&mut self) -> Result<(), Box<dyn Error>> {
+        if let Some(path) = &self.locale_file {
+            let infile = File::open(path)?;
+            self.locale = Locale::from_reader(BufReader::new(infile))?;
+        }
+        if let Some(path) = &self.css_file {
+            self.css = Some(std::fs::read_to_string(path)?);
+        }
+        if let Some(path) = &self.template_file {
+            self.template = Some(std::fs::read_to_string(path)?);
+        }
+        Ok(())
+    }
+
+    /// The same arguments, with the files given to options read anew.
+    fn reload(&self) -> Result<Self, Box<dyn Error>> {
+        let mut args = self.clone();
+        args.read_inputs()?;
+        Ok(args)
+    }
+
+    /// The files read for options, besides the plan.
+    fn inputs(&self) -> impl Iterator<Item = &str> {
+        [&self.locale_file, &self.css_file, &self.template_file]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+    }
+
+    fn path(&self) -> &str {
+        self.path.as_deref().unwrap_or(PLAN_PATH)
+    }
+
+    fn port(&self) -> u16 {
+        self.port.unwrap_or(8080)
+    }
+
+    fn output(&self) -> &str {
+        self.output.as_deref().unwrap_or(OUTPUT_PATH)
+    }
+
+    /// Picks the renderer matching the extension of the output file.
+    fn renderer(&self) -> Result<Box<dyn Render>, Box<dyn Error>> {
+        let output = self.output();
+        let extension = Path::new(output)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+
+        self.renderer_for(&extension).ok_or_else(|| {
+            format!(
+                "don't know how to render `{output}`, expected .html, .pdf, .svg, .md, .adoc, .csv, .tsv or .ics"
+            )
+            .into()
+        })
+    }
+
+    /// The renderer for files with the given lowercase extension.
+    fn renderer_for(&self, extension: &str) -> Option<Box<dyn Render>> {
+        let locale = self.locale.clone();
+
+        Some(match extension {
+            "html" | "htm" => Box::new(self.html()),
+            "pdf" => Box::new(Pdf::new().locale(locale).page_size(self.page_size)),
+            "svg" => Box::new(Svg::new().locale(locale)),
+            "md" | "markdown" => Box::new(Markdown::new().locale(locale).layout(self.layout)),
+            "adoc" | "asciidoc" => Box::new(AsciiDoc::new().locale(locale).layout(self.layout)),
+            "csv" => Box::new(Csv::new().grid(self.grid)),
+            "tsv" => Box::new(Csv::tsv().grid(self.grid)),
+            "ics" => Box::new(self.calendar()),
+            _ => return None,
+        })
+    }
+
+    /// A terminal renderer fitted to the width of the terminal, using colors
+    /// when writing to one unless `NO_COLOR` is set. The width falls back to
+    /// `COLUMNS`, then 80, when the size of the terminal is unknown.
+    fn terminal(&self) -> Terminal {
+        let width = self
+            .width
+            .or_else(|| Some(stty::terminal_size()?.0))
+            .or_else(|| std::env::var("COLUMNS").ok()?.parse().ok())
+            .unwrap_or(80);
+        let colors = !self.no_color
+            && std::env::var_os("NO_COLOR").is_none()
+            && std::io::stdout().is_terminal();
+
+        Terminal::new()
+            .locale(self.locale.clone())
+            .width(width)
+            .colors(colors)
+    }
+
+    fn calendar(&self) -> ICalendar {
+        let calendar = ICalendar::new().locale(self.locale.clone());
+        match &self.calendar_domain {
+            Some(domain) => calendar.domain(domain.clone()),
+            None => calendar,
+        }
+    }
+
+    fn html(&self) -> Html {
+        let mut html = Html::new()
+            .locale(self.locale.clone())
+            .theme(self.theme)
+            .editor(self.editor);
+        if let Some(css) = &self.css {
+            html = html.css(css.clone());
+        }
+        if let Some(template) = &self.template {
+            html = html.template(template.clone());
+        }
+        html
+    }
+}
+
+/// Parses the source of a plan file in the format of its path, describing
+/// every problem found in it, each prefixed with the path. Only JSON plans
+/// are validated with locations; TOML and YAML plans report their first
+/// problem, with its path in the document.
+fn parse_plan(path: &str, source: &str) -> Result<WeekPlan, Vec<String>> {
+    let format = Format::from_path(path).map_err(|err| vec![err.to_string()])?;
+    if format != Format::Json {
+        return format
+            .parse_plan(source)
+            .map_err(|err| vec![format!("{path}: {err}")]);
+    }
+
+    validation::validate(source).map_err(|report| {
+        report
+            .problems()
+            .iter()
+            .map(|problem| format!("{path}:{problem}"))
+            .collect()
+    })
+}
+
+/// Reads a plan file, describing every problem found in it on error, one per
+/// line.
+pub fn read_plan(path: &str) -> Result<WeekPlan, String> {
+    let source = std::fs::read_to_string(path).map_err(|err| format!("{path}: {err}"))?;
+    parse_plan(path, &source).map_err(|problems| problems.join("\n"))
+}
+
+/// Loads a plan file, printing every problem found in it.
+fn load(path: &str) -> Result<WeekPlan, Box<dyn Error>> {
+    let source = std::fs::read_to_string(path)?;
+
+    parse_plan(path, &source).map_err(|problems| {
+        for problem in &problems {
+            eprintln!("{problem}");
+        }
+        let count = problems.len();
+        format!("{path} has {count} problem(s)").into()
+    })
+}
+
+fn check(path: &str) -> Result<(), Box<dyn Error>> {
+    load(path)?;
+    println!("{path} is valid");
+    Ok(())
+}
+
+fn render(args: &Args) -> Result<(), Box<dyn Error>> {
+    let week_plan = load(args.path())?;
+    let renderer = args.renderer()?;
+
+    let outfile = File::create(args.output())?;
+    let mut writer = BufWriter::new(outfile);
+    renderer.render(&week_plan, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Prints a plan as a table on the standard output.
+fn show(args: &Args) -> Result<(), Box<dyn Error>> {
+    let week_plan = load(args.path())?;
+    let mut stdout = std::io::stdout().lock();
+    args.terminal().render(&week_plan, &mut stdout)?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Replaces the bookings of a plan file with those of a spreadsheet, leaving
+/// the file untouched if any row is invalid.
+fn import(args: &Args) -> Result<(), Box<dyn Error>> {
+    let from = args
+        .from
+        .as_deref()
+        .ok_or_else(|| format!("missing `--from` file\n{USAGE}"))?;
+    let path = args.path();
+    let mut week_plan = load(path)?;
+
+    let csv = if from.to_lowercase().ends_with(".tsv") {
+        Csv::tsv()
+    } else {
+        Csv::new()
     };
+    let count = csv
+        .import(week_plan.clear(), &std::fs::read_to_string(from)?)
+        .map_err(|err| format!("{from}: {err}"))?;
+
+    format::save(path, &week_plan)?;
+
+    println!("Imported {count} booking(s) from {from} into {path}");
+    Ok(())
+}
+
+/// Rewrites a plan file in the latest format version.
+fn migrate(path: &str) -> Result<(), Box<dyn Error>> {
+    let document = Format::from_path(path)?.parse(&std::fs::read_to_string(path)?)?;
+    let version = migration::version_of(&document)?;
+    let week_plan = migration::from_value(document)?;
 
-    let mut outfile = File::create("output/week_plan.html")?;
-    write!(outfile, "{}", week_plan.to_html())?;
+    if version == migration::CURRENT_VERSION {
+        println!("{path} is already at version {version}");
+        return Ok(());
+    }
+
+    format::save(path, &week_plan)?;
+
+    println!(
+        "Migrated {path} from version {version} to {}",
+        migration::CURRENT_VERSION
+    );
     Ok(())
 }
+
+/// When a file was last modified, if it exists.
+fn modified(path: &str) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+}
+
+/// Renders the plan again whenever it or a file given to an option changes,
+/// until the process is stopped. Problems are printed and rendering resumes
+/// once they are fixed. With `--live-reload`, the server runs meanwhile.
+fn watch(args: &Args) -> Result<(), Box<dyn Error>> {
+    let listener = if args.live_reload {
+        Some(server::bind(args)?)
+    } else {
+        None
+    };
+
+    thread::scope(|scope| {
+        let mut server = listener.map(|listener| scope.spawn(|| server::run(args, listener)));
+
+        let stamps = || -> Vec<Option<SystemTime>> {
+            std::iter::once(args.path())
+                .chain(args.inputs())
+                .map(modified)
+                .collect()
+        };
+        println!("Watching {} (Ctrl-C to stop)", args.path());
+
+        let mut rendered = None;
+        let mut previous = stamps();
+        loop {
+            // Waits for files to stay unchanged for an interval, so that
+            // files being written are not read halfway
+            thread::sleep(WATCH_INTERVAL);
+            if let Some(server) = server.take_if(|server| server.is_finished()) {
+                return match server.join() {
+                    Ok(Ok(())) => Err("the live reload server stopped".into()),
+                    Ok(Err(err)) => Err(format!("the live reload server failed: {err}").into()),
+                    Err(panic) => std::panic::resume_unwind(panic),
+                };
+            }
+
+            let current = stamps();
+            if current != previous || rendered.as_ref() == Some(&current) {
+                previous = current;
+                continue;
+            }
+
+            let result = args.reload().and_then(|args| render(&args));
+            match result {
+                Ok(()) => println!("Rendered {} to {}", args.path(), args.output()),
+                Err(err) => eprintln!("error: {err}"),
+            }
+            rendered = Some(current);
+        }
+    })
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse(std::env::args().skip(1))?;
+    if args.help {
+        println!("{USAGE}");
+        return Ok(());
+    }
+
+    match args.command.as_deref() {
+        None | Some("render") => render(&args),
+        Some("show") => show(&args),
+        Some("edit") => tui::edit(&args),
+        Some("check") => check(args.path()),
+        Some("migrate") => migrate(args.path()),
+        Some("import") => import(&args),
+        Some("serve") => Ok(server::serve(&args)?),
+        Some("watch") => watch(&args),
+        Some("schema") => {
+            println!("{:#}", schema::schema());
+            Ok(())
+        }
+        Some(command) => Err(format!("unknown command `{command}`\n{USAGE}").into()),
+    }
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
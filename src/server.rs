@@ -0,0 +1,395 @@
+//! A small HTTP/1.1 server rendering the plan file on each request.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::{Duration, UNIX_EPOCH};
+
+use weekly_planner::weekplan::format::Format;
+use weekly_planner::weekplan::render::Render;
+use weekly_planner::WeekPlan;
+
+use crate::{read_plan, Args};
+
+mod api;
+
+/// Requests larger than this are refused.
+const MAX_REQUEST_SIZE: u64 = 1 << 20;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// Reads a request, or returns `None` if the client closed the connection
+    /// first.
+    pub fn read(reader: &mut impl BufRead) -> io::Result<Option<Request>> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg);
+        let mut reader = reader.take(MAX_REQUEST_SIZE);
+
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let mut parts = line.split_whitespace();
+        let (Some(method), Some(target), Some(_version)) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(invalid("malformed request line"));
+        };
+        let method = method.to_owned();
+        // The query string is not used
+        let path = target.split('?').next().unwrap_or_default().to_owned();
+
+        let mut headers = Vec::new();
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 {
+                return Err(invalid("unexpected end of headers"));
+            }
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            let (name, value) = line
+                .split_once(':')
+                .ok_or_else(|| invalid("malformed header"))?;
+            headers.push((name.trim().to_lowercase(), value.trim().to_owned()));
+        }
+
+        let length = headers
+            .iter()
+            .find(|(name, _)| name == "content-length")
+            .map(|(_, value)| value.parse::<u64>())
+            .transpose()
+            .map_err(|_| invalid("invalid content length"))?
+            .unwrap_or(0);
+        let mut body = Vec::new();
+        reader.take(length).read_to_end(&mut body)?;
+        if body.len() as u64 != length {
+            return Err(invalid("truncated body"));
+        }
+
+        Ok(Some(Request {
+            method,
+            path,
+            headers,
+            body,
+        }))
+    }
+}
+
+impl Request {
+    /// The value of a header, whose name must be lowercase.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn new(status: u16, content_type: &'static str, body: impl Into<Vec<u8>>) -> Self {
+        Response {
+            status,
+            content_type,
+            body: body.into(),
+        }
+    }
+
+    pub fn text(status: u16, body: impl Into<String>) -> Self {
+        let mut body = body.into();
+        if !body.ends_with('\n') {
+            body.push('\n');
+        }
+        Response::new(status, "text/plain; charset=utf-8", body)
+    }
+
+    pub fn json(status: u16, value: &serde_json::Value) -> Self {
+        Response::new(status, "application/json", format!("{value:#}\n"))
+    }
+
+    pub fn write(&self, out: &mut impl Write, with_body: bool) -> io::Result<()> {
+        write!(
+            out,
+            "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+            self.status,
+            reason(self.status),
+            self.content_type,
+            self.body.len()
+        )?;
+        if with_body {
+            out.write_all(&self.body)?;
+        }
+        out.flush()
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        415 => "Unsupported Media Type",
+        422 => "Unprocessable Entity",
+        500 => "Internal Server Error",
+        _ => "",
+    }
+}
+
+/// Serves the plan of `args` until the process is stopped, one connection at
+/// a time.
+pub fn serve(args: &Args) -> io::Result<()> {
+    let listener = bind(args)?;
+    run(args, listener)
+}
+
+/// Listens on the port of `args`.
+pub fn bind(args: &Args) -> io::Result<TcpListener> {
+    let listener = TcpListener::bind(("127.0.0.1", args.port()))?;
+    println!(
+        "Serving {} on http://{}/ (Ctrl-C to stop)",
+        args.path(),
+        listener.local_addr()?
+    );
+    Ok(listener)
+}
+
+/// Serves the plan of `args` on `listener` until the process is stopped.
+pub fn run(args: &Args, listener: TcpListener) -> io::Result<()> {
+    for stream in listener.incoming() {
+        let result = stream.and_then(|stream| handle_connection(args, stream));
+        if let Err(err) = result {
+            eprintln!("warning: {err}");
+        }
+    }
+    Ok(())
+}
+
+fn handle_connection(args: &Args, stream: TcpStream) -> io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut reader = BufReader::new(&stream);
+
+    let response = match Request::read(&mut reader) {
+        Ok(Some(request)) => {
+            let response = handle(args, &request);
+            let with_body = request.method != "HEAD";
+            return response.write(&mut &stream, with_body);
+        }
+        Ok(None) => return Ok(()),
+        Err(err) => Response::text(400, err.to_string()),
+    };
+    response.write(&mut &stream, true)
+}
+
+/// Routes a request: `/` is the HTML page and `/plan.EXT` the plan in the
+/// format of the extension, as with `main -o`, plus `json`, `toml` and `yaml`
+/// for the plan file itself and `txt` for the terminal table. `/api/` edits
+/// the plan, see [`api`], and `/version` changes whenever the plan file does.
+pub fn handle(args: &Args, request: &Request) -> Response {
+    if request.path.starts_with("/api/") {
+        return api::handle(args.path(), request);
+    }
+    if request.method != "GET" && request.method != "HEAD" {
+        return Response::text(405, format!("{} is not allowed", request.method));
+    }
+
+    let extension = match request.path.as_str() {
+        "/" | "/index.html" => "html",
+        "/version" => return Response::text(200, version(args.path())),
+        path => match path.strip_prefix("/plan.") {
+            Some(extension) => extension,
+            None => return Response::text(404, format!("{path} not found")),
+        },
+    };
+
+    let week_plan = match read_plan(args.path()) {
+        Ok(week_plan) => week_plan,
+        Err(msg) => return Response::text(500, msg),
+    };
+    render(args, &week_plan, extension)
+        .unwrap_or_else(|| Response::text(404, format!("{} not found", request.path)))
+}
+
+fn render(args: &Args, week_plan: &WeekPlan, extension: &str) -> Option<Response> {
+    let format = match extension {
+        "json" => Some(Format::Json),
+        "toml" => Some(Format::Toml),
+        "yaml" | "yml" => Some(Format::Yaml),
+        _ => None,
+    };
+    if let Some(format) = format {
+        return Some(match format.write_plan(week_plan) {
+            Ok(source) => Response::new(200, content_type(extension), source),
+            Err(err) => Response::text(404, err.to_string()),
+        });
+    }
+
+    if extension == "txt" {
+        let text = args.terminal().colors(false).render_to_vec(week_plan);
+        return Some(Response::new(200, content_type(extension), text));
+    }
+
+    let renderer = args.renderer_for(extension)?;
+    let mut body = renderer.render_to_vec(week_plan);
+    if args.live_reload && matches!(extension, "html" | "htm") {
+        let end = body
+            .windows(7)
+            .rposition(|tag| tag == b"</body>")
+            .unwrap_or(body.len());
+        body.splice(end..end, LIVE_RELOAD_SCRIPT.bytes());
+    }
+    Some(Response::new(200, content_type(extension), body))
+}
+
+/// Polls `/version` and reloads the page when it changes.
+const LIVE_RELOAD_SCRIPT: &str = r#"<script>
+    (async function () {
+        let version = null;
+        for (;;) {
+            try {
+                const response = await fetch("/version", { cache: "no-store" });
+                const current = await response.text();
+                if (version !== null && current !== version) {
+                    location.reload();
+                    return;
+                }
+                version = current;
+            } catch (err) {
+                // The server is restarting, try again later
+            }
+            await new Promise((resolve) => setTimeout(resolve, 1000));
+        }
+    })();
+</script>
+"#;
+
+/// Identifies the current content of the plan file by its modification time.
+fn version(path: &str) -> String {
+    crate::modified(path)
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or_else(|| "0".to_owned(), |time| time.as_nanos().to_string())
+}
+
+fn content_type(extension: &str) -> &'static str {
+    match extension {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "json" => "application/json",
+        "toml" => "application/toml",
+        "yaml" | "yml" => "application/yaml",
+        "pdf" => "application/pdf",
+        "svg" => "image/svg+xml",
+        "md" | "markdown" => "text/markdown; charset=utf-8",
+        "adoc" | "asciidoc" => "text/asciidoc; charset=utf-8",
+        "csv" => "text/csv; charset=utf-8",
+        "tsv" => "text/tab-separated-values; charset=utf-8",
+        "ics" => "text/calendar; charset=utf-8",
+        _ => "text/plain; charset=utf-8",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use weekly_planner::weekplan::{Time, Weekday};
+
+    #[test]
+    fn read_request() {
+        let raw = b"POST /plan.json?x=1 HTTP/1.1\r\nHost: localhost\r\nContent-Length: 4\r\n\r\nbodyextra";
+        let request = Request::read(&mut &raw[..]).unwrap().unwrap();
+        assert_eq!(
+            request,
+            Request {
+                method: "POST".into(),
+                path: "/plan.json".into(),
+                headers: vec![
+                    ("host".into(), "localhost".into()),
+                    ("content-length".into(), "4".into())
+                ],
+                body: b"body".to_vec(),
+            }
+        );
+
+        assert!(Request::read(&mut &b""[..]).unwrap().is_none());
+        assert!(Request::read(&mut &b"GET\r\n\r\n"[..]).is_err());
+        assert!(
+            Request::read(&mut &b"GET / HTTP/1.1\r\nContent-Length: 9\r\n\r\nbody"[..]).is_err()
+        );
+    }
+
+    #[test]
+    fn write_response() {
+        let mut out = Vec::new();
+        Response::text(404, "/x not found")
+            .write(&mut out, true)
+            .unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("HTTP/1.1 404 Not Found\r\n"));
+        assert!(out.contains("Content-Length: 13\r\n"));
+        assert!(out.ends_with("\r\n\r\n/x not found\n"));
+    }
+
+    #[test]
+    fn routes() {
+        let mut week_plan = WeekPlan::new(Time::new(8, 30).unwrap(), 90, 4).unwrap();
+        week_plan
+            .try_insert(Weekday::Monday, Time::new(8, 30).unwrap(), "A".into())
+            .unwrap();
+        let path =
+            std::env::temp_dir().join(format!("weekly_planner-{}-routes.json", std::process::id()));
+        std::fs::write(&path, Format::Json.write_plan(&week_plan).unwrap()).unwrap();
+        let path = path.to_string_lossy().into_owned();
+
+        let args = Args::parse(["serve".to_owned(), path.clone()]).unwrap();
+        let get = |path: &str| {
+            handle(
+                &args,
+                &Request {
+                    method: "GET".into(),
+                    path: path.into(),
+                    headers: Vec::new(),
+                    body: Vec::new(),
+                },
+            )
+        };
+
+        let page = get("/");
+        assert_eq!(
+            (page.status, page.content_type),
+            (200, "text/html; charset=utf-8")
+        );
+        assert_eq!(
+            get("/plan.ics").content_type,
+            "text/calendar; charset=utf-8"
+        );
+        assert!(get("/plan.json").body.starts_with(b"{\n  \"version\": 2"));
+        assert_eq!(get("/plan.exe").status, 404);
+        assert!(!page.body.windows(6).any(|tag| tag == b"/version"));
+        assert_eq!(get("/version").status, 200);
+
+        let args = Args::parse(["serve".to_owned(), path, "--live-reload".to_owned()]).unwrap();
+        let request = Request {
+            method: "GET".into(),
+            path: "/".into(),
+            headers: Vec::new(),
+            body: Vec::new(),
+        };
+        let page = String::from_utf8(handle(&args, &request).body).unwrap();
+        assert!(page.contains(&format!("{LIVE_RELOAD_SCRIPT}</body>")));
+        assert_eq!(get("/other").status, 404);
+    }
+}
@@ -0,0 +1,395 @@
+//! JSON endpoints editing the bookings of the plan file:
+//!
+//! - `GET /api/bookings` lists the bookings,
+//! - `POST /api/bookings` books `{"weekday", "start", "slots", "activity"}`,
+//!   `slots` being 1 if missing,
+//! - `PATCH /api/bookings/WEEKDAY/HH:MM` moves a booking to
+//!   `{"weekday", "start"}`, either being kept if missing,
+//! - `DELETE /api/bookings/WEEKDAY/HH:MM` frees a slot.
+//!
+//! Changes go through the same checks as [`WeekPlan::try_insert`] and are
+//! saved to the plan file only if they all succeed. Errors are answered as
+//! `{"error": KIND, "message": TEXT}`, plus the slot at fault if any.
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use weekly_planner::weekplan::{
+    self, Activity, Error, ParseTimeError, ParseWeekdayError, Time, Weekday,
+};
+use weekly_planner::WeekPlan;
+
+use super::{Request, Response};
+use crate::read_plan;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NewBooking {
+    weekday: Weekday,
+    start: Time,
+    #[serde(default = "one")]
+    slots: u8,
+    activity: Activity,
+}
+
+fn one() -> u8 {
+    1
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Move {
+    weekday: Option<Weekday>,
+    start: Option<Time>,
+}
+
+/// Handles a request to `/api/...` on the plan file at `path`.
+pub fn handle(path: &str, request: &Request) -> Response {
+    let segments: Vec<&str> = request
+        .path
+        .trim_start_matches("/api/")
+        .trim_end_matches('/')
+        .split('/')
+        .collect();
+
+    let response = match (request.method.as_str(), segments.as_slice()) {
+        ("GET" | "HEAD", ["bookings"]) => list(path),
+        ("POST", ["bookings"]) => add(path, request),
+        ("PATCH", ["bookings", weekday, start]) => {
+            slot(weekday, start).and_then(|slot| move_booking(path, slot, request))
+        }
+        ("DELETE", ["bookings", weekday, start]) => {
+            slot(weekday, start).and_then(|slot| delete(path, slot))
+        }
+        (_, ["bookings"] | ["bookings", _, _]) => Err(error(
+            405,
+            "method_not_allowed",
+            format!("{} is not allowed on {}", request.method, request.path),
+        )),
+        _ => Err(error(
+            404,
+            "not_found",
+            format!("{} not found", request.path),
+        )),
+    };
+    response.unwrap_or_else(|response| response)
+}
+
+type Result<T> = std::result::Result<T, Response>;
+
+fn list(path: &str) -> Result<Response> {
+    let week_plan = load(path)?;
+    let bookings: Vec<Value> = week_plan
+        .bookings()
+        .into_iter()
+        .map(|(weekday, start, activity)| booking(weekday, start, activity))
+        .collect();
+    Ok(Response::json(200, &Value::from(bookings)))
+}
+
+fn add(path: &str, request: &Request) -> Result<Response> {
+    let new: NewBooking = body(request)?;
+    if new.activity.trim().is_empty() {
+        return Err(error(422, "missing_activity", "the activity is empty"));
+    }
+    if new.slots == 0 {
+        return Err(error(
+            422,
+            "invalid_slots",
+            "at least a slot must be booked",
+        ));
+    }
+
+    let mut week_plan = load(path)?;
+    week_plan
+        .try_insert_range(new.weekday, (new.start, new.slots), new.activity.clone())
+        .map_err(booking_error)?;
+    save(path, &week_plan)?;
+
+    let bookings: Vec<Value> = (0..u16::from(new.slots))
+        .filter_map(|i| new.start.try_sum(i * week_plan.slot_duration()))
+        .map(|start| booking(new.weekday, start, &new.activity))
+        .collect();
+    Ok(Response::json(201, &Value::from(bookings)))
+}
+
+fn move_booking(
+    path: &str,
+    (weekday, start): (Weekday, Time),
+    request: &Request,
+) -> Result<Response> {
+    let to: Move = body(request)?;
+    let to = (to.weekday.unwrap_or(weekday), to.start.unwrap_or(start));
+
+    let mut week_plan = load(path)?;
+    let activity = week_plan
+        .remove(weekday, start)
+        .ok_or_else(|| free_slot(weekday, start))?;
+    week_plan
+        .try_insert(to.0, to.1, activity.clone())
+        .map_err(booking_error)?;
+    save(path, &week_plan)?;
+
+    Ok(Response::json(200, &booking(to.0, to.1, &activity)))
+}
+
+fn delete(path: &str, (weekday, start): (Weekday, Time)) -> Result<Response> {
+    let mut week_plan = load(path)?;
+    let activity = week_plan
+        .remove(weekday, start)
+        .ok_or_else(|| free_slot(weekday, start))?;
+    save(path, &week_plan)?;
+
+    Ok(Response::json(200, &booking(weekday, start, &activity)))
+}
+
+fn booking(weekday: Weekday, start: Time, activity: &str) -> Value {
+    json!({ "weekday": weekday, "start": start, "activity": activity })
+}
+
+fn load(path: &str) -> Result<WeekPlan> {
+    read_plan(path).map_err(|msg| error(500, "invalid_plan", msg))
+}
+
+fn save(path: &str, week_plan: &WeekPlan) -> Result<()> {
+    weekplan::format::save(path, week_plan)
+        .map_err(|err| error(500, "write_failed", err.to_string()))
+}
+
+/// Reads a JSON request body.
+fn body<T: for<'de> Deserialize<'de>>(request: &Request) -> Result<T> {
+    let content_type = request.header("content-type").unwrap_or_default();
+    let media_type = content_type.split(';').next().unwrap_or_default().trim();
+    if !media_type.eq_ignore_ascii_case("application/json") {
+        return Err(error(
+            415,
+            "unsupported_media_type",
+            "the body must be application/json",
+        ));
+    }
+
+    serde_json::from_slice(&request.body).map_err(|err| error(400, "invalid_body", err.to_string()))
+}
+
+/// Parses the slot of a `/WEEKDAY/HH:MM` path.
+fn slot(weekday: &str, start: &str) -> Result<(Weekday, Time)> {
+    let invalid = |msg: String| error(404, "not_found", msg);
+    let weekday = decode(weekday)
+        .parse()
+        .map_err(|err: ParseWeekdayError| invalid(err.to_string()))?;
+    let start = decode(start)
+        .parse()
+        .map_err(|err: ParseTimeError| invalid(err.to_string()))?;
+    Ok((weekday, start))
+}
+
+/// Decodes the `%XX` escapes of a path segment, as clients may send `08%3A30`.
+fn decode(segment: &str) -> String {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escape = bytes
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escape {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn error(status: u16, kind: &str, message: impl Into<String>) -> Response {
+    let message: String = message.into();
+    Response::json(status, &json!({ "error": kind, "message": message }))
+}
+
+fn free_slot(weekday: Weekday, start: Time) -> Response {
+    Response::json(
+        404,
+        &json!({
+            "error": "not_booked",
+            "message": format!("Slot {weekday} {start} is not booked"),
+            "weekday": weekday,
+            "start": start,
+        }),
+    )
+}
+
+/// Answers a [`WeekPlan`] error: 409 for booked slots and 422 for slots the
+/// plan doesn't have.
+fn booking_error(err: Error) -> Response {
+    let message = err.to_string();
+    match err {
+        Error::AlreadyBooked(slot) => Response::json(
+            409,
+            &json!({
+                "error": "already_booked",
+                "message": message,
+                "weekday": slot.weekday(),
+                "start": slot.time(),
+            }),
+        ),
+        Error::InvalidSlot(start) => Response::json(
+            422,
+            &json!({ "error": "invalid_slot", "message": message, "start": start }),
+        ),
+        Error::OutOfBounds => error(422, "out_of_bounds", message),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use weekly_planner::weekplan::format::Format;
+
+    /// A plan file with a booking on Monday at 08:30, unique to `name`.
+    fn plan_file(name: &str) -> String {
+        let mut week_plan = WeekPlan::new(Time::new(8, 30).unwrap(), 90, 4).unwrap();
+        week_plan
+            .try_insert(Weekday::Monday, Time::new(8, 30).unwrap(), "A".into())
+            .unwrap();
+
+        let path =
+            std::env::temp_dir().join(format!("weekly_planner-{}-{name}.json", std::process::id()));
+        std::fs::write(&path, Format::Json.write_plan(&week_plan).unwrap()).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    fn request(path: &str, method: &str, target: &str, body: &str) -> (u16, Value) {
+        let request = Request {
+            method: method.into(),
+            path: target.into(),
+            headers: vec![("content-type".into(), "application/json".into())],
+            body: body.into(),
+        };
+        let response = handle(path, &request);
+        assert_eq!(response.content_type, "application/json");
+        (
+            response.status,
+            serde_json::from_slice(&response.body).unwrap(),
+        )
+    }
+
+    #[test]
+    fn edit() {
+        let path = plan_file("edit");
+        let request = |method, target, body| request(&path, method, target, body);
+
+        let (status, created) = request(
+            "POST",
+            "/api/bookings",
+            r#"{"weekday": "Tuesday", "start": "10:00", "slots": 2, "activity": "B"}"#,
+        );
+        assert_eq!(status, 201);
+        assert_eq!(
+            created[1],
+            json!({"weekday": "Tuesday", "start": "11:30", "activity": "B"})
+        );
+
+        let (status, moved) = request(
+            "PATCH",
+            "/api/bookings/Monday/08%3A30",
+            r#"{"start": "13:00"}"#,
+        );
+        assert_eq!(status, 200);
+        assert_eq!(
+            moved,
+            json!({"weekday": "Monday", "start": "13:00", "activity": "A"})
+        );
+
+        let (status, _) = request("DELETE", "/api/bookings/Tuesday/10:00", "");
+        assert_eq!(status, 200);
+
+        let (status, bookings) = request("GET", "/api/bookings", "");
+        assert_eq!(status, 200);
+        assert_eq!(
+            bookings,
+            json!([
+                {"weekday": "Monday", "start": "13:00", "activity": "A"},
+                {"weekday": "Tuesday", "start": "11:30", "activity": "B"},
+            ])
+        );
+
+        let saved = read_plan(&path).unwrap();
+        assert_eq!(saved.bookings().len(), 2);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn errors() {
+        let path = plan_file("errors");
+        let before = std::fs::read_to_string(&path).unwrap();
+        let request = |method, target, body| request(&path, method, target, body);
+        let add = |body| request("POST", "/api/bookings", body);
+
+        let (status, err) = add(r#"{"weekday": "Monday", "start": "08:30", "activity": "B"}"#);
+        assert_eq!(status, 409);
+        assert_eq!(
+            err,
+            json!({
+                "error": "already_booked",
+                "message": "Slot Monday 08:30 already booked",
+                "weekday": "Monday",
+                "start": "08:30",
+            })
+        );
+
+        // The first slot is free but the second one is past the last slot:
+        // nothing is booked
+        let (status, err) =
+            add(r#"{"weekday": "Sunday", "start": "13:00", "slots": 2, "activity": "B"}"#);
+        assert_eq!(status, 422);
+        assert_eq!(
+            (&err["error"], &err["start"]),
+            (&json!("invalid_slot"), &json!("14:30"))
+        );
+
+        let (status, err) = add(r#"{"weekday": "Monday", "start": "10:00", "activity": " "}"#);
+        assert_eq!((status, &err["error"]), (422, &json!("missing_activity")));
+        let (status, err) = add(r#"{"weekday": "Monday", "start": "10:00"}"#);
+        assert_eq!((status, &err["error"]), (400, &json!("invalid_body")));
+        let (status, _) = add("{");
+        assert_eq!(status, 400);
+
+        let (status, err) = request(
+            "PATCH",
+            "/api/bookings/Monday/08:30",
+            r#"{"start": "09:00"}"#,
+        );
+        assert_eq!((status, &err["error"]), (422, &json!("invalid_slot")));
+        let (status, err) = request("PATCH", "/api/bookings/Friday/08:30", "{}");
+        assert_eq!((status, &err["error"]), (404, &json!("not_booked")));
+
+        let (status, err) = request("DELETE", "/api/bookings/Friday/08:30", "");
+        assert_eq!((status, &err["error"]), (404, &json!("not_booked")));
+        let (status, err) = request("DELETE", "/api/bookings/Funday/08:30", "");
+        assert_eq!((status, &err["error"]), (404, &json!("not_found")));
+        let (status, _) = request("PUT", "/api/bookings", "");
+        assert_eq!(status, 405);
+        let (status, _) = request("GET", "/api/other", "");
+        assert_eq!(status, 404);
+
+        let mut form = Request {
+            method: "POST".into(),
+            path: "/api/bookings".into(),
+            headers: vec![("content-type".into(), "text/plain".into())],
+            body: b"{}".to_vec(),
+        };
+        assert_eq!(handle(&path, &form).status, 415);
+        form.headers.clear();
+        assert_eq!(handle(&path, &form).status, 415);
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), before);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,29 @@
+//! Settings and size of the terminal, through the `stty` command.
+
+use std::io;
+use std::process::{Command, Stdio};
+
+/// Runs `stty` on the terminal of the standard input.
+pub fn stty(args: &[&str]) -> io::Result<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(io::Error::other(format!(
+            "stty {} failed: {}",
+            args.join(" "),
+            stderr.trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// The size of the terminal as `(columns, lines)`, if the standard input is
+/// one.
+pub fn terminal_size() -> Option<(usize, usize)> {
+    let size = stty(&["size"]).ok()?;
+    let (lines, columns) = size.trim().split_once(' ')?;
+    Some((columns.parse().ok()?, lines.parse().ok()?))
+}
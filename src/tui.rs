@@ -0,0 +1,603 @@
+//! A full-screen editor for the plan file, drawn with ANSI escapes on a
+//! terminal switched to raw mode with `stty`.
+
+use std::error::Error;
+use std::fs::OpenOptions;
+use std::io::{self, IsTerminal, Read, Write};
+
+use weekly_planner::weekplan::history::{self, Edit, EditError, Entry, History};
+use weekly_planner::weekplan::locale::Locale;
+use weekly_planner::weekplan::{self, Activity, Color, Time, Weekday};
+use weekly_planner::WeekPlan;
+
+use crate::stty::{stty, terminal_size};
+use crate::Args;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const REVERSE: &str = "\x1b[7m";
+const RED: &str = "\x1b[31m";
+
+const HELP: &str = "arrows move  Enter name  + extend  d free  u undo  r redo  s save  q quit";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Enter,
+    Backspace,
+    Delete,
+    Esc,
+    /// Ctrl-C, read as a key in raw mode.
+    Interrupt,
+    Char(char),
+}
+
+/// Decodes the keys of a chunk of terminal input. Escape sequences are
+/// expected to arrive in one chunk, a lone escape being the Esc key.
+pub fn parse_keys(bytes: &[u8]) -> Vec<Key> {
+    let mut keys = Vec::new();
+    let mut rest = bytes;
+
+    while let Some(&byte) = rest.first() {
+        let (key, len) = match (byte, rest.get(1), rest.get(2)) {
+            (0x1b, Some(b'[' | b'O'), Some(b'A')) => (Some(Key::Up), 3),
+            (0x1b, Some(b'[' | b'O'), Some(b'B')) => (Some(Key::Down), 3),
+            (0x1b, Some(b'[' | b'O'), Some(b'C')) => (Some(Key::Right), 3),
+            (0x1b, Some(b'[' | b'O'), Some(b'D')) => (Some(Key::Left), 3),
+            (0x1b, Some(b'['), Some(b'3')) if rest.get(3) == Some(&b'~') => (Some(Key::Delete), 4),
+            // Other sequences are skipped up to their final byte
+            (0x1b, Some(b'['), _) => {
+                let end = rest[2..]
+                    .iter()
+                    .position(|byte| (0x40..=0x7e).contains(byte))
+                    .map_or(rest.len(), |end| end + 3);
+                (None, end)
+            }
+            (0x1b, _, _) => (Some(Key::Esc), 1),
+            (b'\r' | b'\n', _, _) => (Some(Key::Enter), 1),
+            (0x7f | 0x08, _, _) => (Some(Key::Backspace), 1),
+            (0x03, _, _) => (Some(Key::Interrupt), 1),
+            (byte, _, _) if byte < 0x20 => (None, 1),
+            (byte, _, _) => {
+                let len = match byte {
+                    0xf0.. => 4,
+                    0xe0.. => 3,
+                    0xc0.. => 2,
+                    _ => 1,
+                };
+                let len = len.min(rest.len());
+                let key = std::str::from_utf8(&rest[..len])
+                    .ok()
+                    .and_then(|text| text.chars().next())
+                    .map(Key::Char);
+                (key, len)
+            }
+        };
+
+        keys.extend(key);
+        rest = &rest[len..];
+    }
+    keys
+}
+
+/// What the caller of [`Editor::handle`] should do next.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Flow {
+    Continue,
+    Save,
+    Quit,
+}
+
+#[derive(Debug)]
+enum Status {
+    Info(String),
+    Error(String),
+}
+
+/// The state of the editor, independent of the terminal.
+#[derive(Debug)]
+pub struct Editor {
+    history: History,
+    locale: Locale,
+    path: String,
+    colors: bool,
+    weekdays: Vec<Weekday>,
+    times: Vec<Time>,
+    day: usize,
+    slot: usize,
+    /// The name being typed, if any.
+    name: Option<String>,
+    modified: bool,
+    /// Whether `q` was pressed with unsaved changes.
+    quitting: bool,
+    status: Status,
+}
+
+impl Editor {
+    pub fn new(week_plan: WeekPlan, locale: Locale, path: &str) -> Self {
+        Editor {
+            weekdays: week_plan.weekdays().collect(),
+            times: week_plan.slot_times(),
+            history: History::new(week_plan),
+            locale,
+            path: path.to_owned(),
+            colors: true,
+            day: 0,
+            slot: 0,
+            name: None,
+            modified: false,
+            quitting: false,
+            status: Status::Info(String::new()),
+        }
+    }
+
+    pub fn colors(mut self, colors: bool) -> Self {
+        self.colors = colors;
+        self
+    }
+
+    pub fn week_plan(&self) -> &WeekPlan {
+        self.history.plan()
+    }
+
+    /// Empties the log of the edits made since the last call.
+    pub fn take_log(&mut self) -> Vec<Entry> {
+        self.history.take_log()
+    }
+
+    /// Records that the plan was saved.
+    pub fn saved(&mut self) {
+        self.modified = false;
+        self.status = Status::Info(format!("Saved {}", self.path));
+    }
+
+    pub fn report(&mut self, err: &dyn Error) {
+        self.status = Status::Error(err.to_string());
+    }
+
+    /// The slot under the cursor, if the plan has any.
+    fn cursor(&self) -> Option<(Weekday, Time)> {
+        Some((*self.weekdays.get(self.day)?, *self.times.get(self.slot)?))
+    }
+
+    fn activity(&self) -> Option<&Activity> {
+        let (weekday, time) = self.cursor()?;
+        self.history.plan().get(weekday, time)
+    }
+
+    pub fn handle(&mut self, key: Key) -> Flow {
+        if let Some(name) = &mut self.name {
+            match key {
+                Key::Char(c) => name.push(c),
+                Key::Backspace => {
+                    name.pop();
+                }
+                Key::Enter => {
+                    let name = self.name.take().unwrap_or_default();
+                    let name = name.trim();
+                    self.book((!name.is_empty()).then(|| name.to_owned()));
+                }
+                Key::Esc | Key::Interrupt => self.name = None,
+                _ => {}
+            }
+            return Flow::Continue;
+        }
+
+        let quitting = std::mem::take(&mut self.quitting);
+        match key {
+            Key::Up => self.slot = self.slot.saturating_sub(1),
+            Key::Down => self.slot = (self.slot + 1).min(self.times.len().saturating_sub(1)),
+            Key::Left => self.day = self.day.saturating_sub(1),
+            Key::Right => self.day = (self.day + 1).min(self.weekdays.len().saturating_sub(1)),
+            Key::Char('k') => return self.handle(Key::Up),
+            Key::Char('j') => return self.handle(Key::Down),
+            Key::Char('h') => return self.handle(Key::Left),
+            Key::Char('l') => return self.handle(Key::Right),
+            Key::Enter | Key::Char('i') if self.cursor().is_some() => {
+                self.name = Some(self.activity().cloned().unwrap_or_default());
+            }
+            Key::Char('+') => self.extend(),
+            Key::Char('d') | Key::Delete | Key::Backspace => {
+                if self.activity().is_some() {
+                    self.book(None);
+                } else {
+                    self.status = Status::Info("The slot is free".to_owned());
+                }
+            }
+            Key::Char('u') => self.undo(),
+            Key::Char('r') => self.redo(),
+            Key::Char('s') => return Flow::Save,
+            Key::Char('q') | Key::Interrupt if self.modified && !quitting => {
+                self.quitting = true;
+                self.status =
+                    Status::Info("Unsaved changes: press q again to quit, or s to save".to_owned());
+            }
+            Key::Char('q') | Key::Interrupt => return Flow::Quit,
+            _ => {}
+        }
+        Flow::Continue
+    }
+
+    /// Books the slot under the cursor, or frees it.
+    fn book(&mut self, activity: Option<Activity>) {
+        let Some((weekday, time)) = self.cursor() else {
+            return;
+        };
+        let result = self.history.set(weekday, time, activity).map(|_| ());
+        self.done(result);
+    }
+
+    /// Books the activity under the cursor in the slot following its last
+    /// consecutive one.
+    fn extend(&mut self) {
+        let (Some((weekday, _)), Some(activity)) = (self.cursor(), self.activity().cloned()) else {
+            self.status = Status::Info("Nothing to extend".to_owned());
+            return;
+        };
+
+        let week_plan = self.history.plan();
+        let last = self.times[self.slot..]
+            .iter()
+            .take_while(|&&time| week_plan.get(weekday, time) == Some(&activity))
+            .last()
+            .copied()
+            .unwrap_or(self.times[self.slot]);
+        let next = last
+            .try_sum(week_plan.slot_duration())
+            .filter(|&next| week_plan.is_valid_slot(next))
+            .ok_or(EditError::Plan(weekplan::Error::OutOfBounds));
+
+        let result = next.and_then(|next| {
+            self.history.apply(Edit::Book {
+                weekday,
+                time: next,
+                activity,
+            })?;
+            Ok(())
+        });
+        self.done(result);
+    }
+
+    /// Reports the outcome of an edit.
+    fn done(&mut self, result: Result<(), EditError>) {
+        self.status = match result {
+            Ok(()) => {
+                self.modified = true;
+                Status::Info(String::new())
+            }
+            Err(err) => Status::Error(err.to_string()),
+        };
+    }
+
+    fn undo(&mut self) {
+        let Some(edit) = self.history.undo().cloned() else {
+            self.status = Status::Info("Nothing to undo".to_owned());
+            return;
+        };
+        self.focus(&edit);
+        self.modified = true;
+        self.status = Status::Info(format!("Undone: {edit}"));
+    }
+
+    fn redo(&mut self) {
+        let Some(edit) = self.history.redo().cloned() else {
+            self.status = Status::Info("Nothing to redo".to_owned());
+            return;
+        };
+        self.focus(&edit);
+        self.modified = true;
+        self.status = Status::Info(format!("Redone: {edit}"));
+    }
+
+    /// Moves the cursor to the first slot an edit changed.
+    fn focus(&mut self, edit: &Edit) {
+        let (weekday, time) = match edit {
+            Edit::Book { weekday, time, .. }
+            | Edit::Free { weekday, time, .. }
+            | Edit::Rename { weekday, time, .. } => (*weekday, *time),
+            Edit::Group { edits } => match edits.first() {
+                Some(edit) => return self.focus(edit),
+                None => return,
+            },
+            _ => return,
+        };
+        self.day = self
+            .weekdays
+            .iter()
+            .position(|&day| day == weekday)
+            .unwrap_or(self.day);
+        self.slot = self
+            .times
+            .iter()
+            .position(|&slot| slot == time)
+            .unwrap_or(self.slot);
+    }
+
+    /// Draws the whole screen for a terminal of `width` columns and `height`
+    /// lines.
+    pub fn draw(&self, width: usize, height: usize) -> String {
+        let times: Vec<String> = self
+            .times
+            .iter()
+            .map(|&time| self.locale.time(time).to_string())
+            .collect();
+        let time_width = times.iter().map(|time| width_of(time)).max().unwrap_or(0);
+        let days = self.weekdays.len().max(1);
+        let column = (width.saturating_sub(time_width + 2) / days)
+            .saturating_sub(1)
+            .max(3);
+
+        let mut lines = Vec::new();
+        let modified = if self.modified { " [+]" } else { "" };
+        lines.push(format!(
+            "{BOLD}{}{RESET}",
+            truncate(
+                &format!("{} — {}{modified}", self.locale.title, self.path),
+                width
+            )
+        ));
+        lines.push(String::new());
+
+        let fits = self
+            .weekdays
+            .iter()
+            .all(|&weekday| width_of(self.locale.weekday(weekday)) < column);
+        let mut header = format!(" {:time_width$} ", "");
+        for &weekday in &self.weekdays {
+            let name = if fits {
+                self.locale.weekday(weekday)
+            } else {
+                self.locale.short_weekday(weekday)
+            };
+            header.push_str(&format!("│{BOLD}{}{RESET}", cell(name, column)));
+        }
+        lines.push(header);
+
+        // Scrolls to keep the cursor on screen
+        let rows = height.saturating_sub(lines.len() + 3).max(1);
+        let first = (self.slot + 1).saturating_sub(rows);
+        for (slot, time) in times.iter().enumerate().skip(first).take(rows) {
+            let mut line = format!(" {time:>time_width$} ");
+            for (day, &weekday) in self.weekdays.iter().enumerate() {
+                let activity = self.history.plan().get(weekday, self.times[slot]);
+                let mut style = String::new();
+                if (day, slot) == (self.day, self.slot) {
+                    style.push_str(REVERSE);
+                } else if let Some(activity) = activity.filter(|_| self.colors) {
+                    let color = self.history.plan().color(activity);
+                    style.push_str(&ansi(color, 48));
+                    style.push_str(&ansi(color.contrasting_text(), 38));
+                }
+                let text = cell(activity.map_or("", String::as_str), column);
+                line.push_str(&format!("│{style}{text}{RESET}"));
+            }
+            lines.push(line);
+        }
+
+        lines.push(String::new());
+        lines.push(match (&self.name, &self.status) {
+            (Some(name), _) => format!("{}: {name}▏", self.locale.activity),
+            (None, Status::Info(msg)) => truncate(msg, width),
+            (None, Status::Error(msg)) => format!("{RED}{}{RESET}", truncate(msg, width)),
+        });
+        lines.push(truncate(HELP, width));
+
+        // Clears each line, then whatever is left below the last one
+        let lines: Vec<String> = lines.into_iter().map(|line| line + "\x1b[K").collect();
+        format!("\x1b[H{}\x1b[J", lines.join("\r\n"))
+    }
+}
+
+/// The number of terminal columns `text` takes, assuming every character is
+/// one column wide.
+fn width_of(text: &str) -> usize {
+    text.chars().count()
+}
+
+/// Cuts `text` to `width` characters, ending it with an ellipsis if anything
+/// was removed.
+fn truncate(text: &str, width: usize) -> String {
+    if width_of(text) <= width {
+        return text.to_owned();
+    }
+
+    let kept: String = text.chars().take(width.saturating_sub(1)).collect();
+    format!("{}…", kept.trim_end())
+}
+
+/// `text` in a cell of `width` columns, with a space on each side.
+fn cell(text: &str, width: usize) -> String {
+    let inner = width.saturating_sub(2);
+    format!(" {:<inner$} ", truncate(text, inner))
+}
+
+/// A 24-bit ANSI color, `layer` being 38 for the text and 48 for the
+/// background.
+fn ansi(color: Color, layer: u8) -> String {
+    format!("\x1b[{layer};2;{};{};{}m", color.r, color.g, color.b)
+}
+
+/// Puts the terminal in raw mode on the alternate screen, restoring it when
+/// dropped.
+struct RawMode {
+    saved: String,
+}
+
+impl RawMode {
+    fn enable() -> io::Result<Self> {
+        let saved = stty(&["-g"])?;
+        stty(&["raw", "-echo"])?;
+        print!("\x1b[?1049h\x1b[?25l");
+        io::stdout().flush()?;
+        Ok(RawMode {
+            saved: saved.trim().to_owned(),
+        })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        print!("\x1b[?25h\x1b[?1049l");
+        let _ = io::stdout().flush();
+        let _ = stty(&[&self.saved]);
+    }
+}
+
+/// Saves the plan, and appends the edits made since the last save to the
+/// change log next to it, `PLAN.log`.
+fn save(path: &str, editor: &mut Editor) -> Result<(), Box<dyn Error>> {
+    weekplan::format::save(path, editor.week_plan())?;
+
+    let mut log = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(format!("{path}.log"))?;
+    history::write_log(&editor.take_log(), &mut log)?;
+    Ok(())
+}
+
+/// Edits the plan of `args` until the user quits.
+pub fn edit(args: &Args) -> Result<(), Box<dyn Error>> {
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        return Err("`edit` needs a terminal".into());
+    }
+
+    let week_plan = crate::load(args.path())?;
+    let colors = !args.no_color && std::env::var_os("NO_COLOR").is_none();
+    let mut editor = Editor::new(week_plan, args.locale.clone(), args.path()).colors(colors);
+
+    let _raw_mode = RawMode::enable()?;
+    let mut stdin = io::stdin().lock();
+    let mut buffer = [0; 64];
+    loop {
+        let (width, height) = terminal_size().unwrap_or((80, 24));
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(editor.draw(width, height).as_bytes())?;
+        stdout.flush()?;
+        drop(stdout);
+
+        let len = stdin.read(&mut buffer)?;
+        if len == 0 {
+            return Ok(());
+        }
+        for key in parse_keys(&buffer[..len]) {
+            match editor.handle(key) {
+                Flow::Continue => {}
+                Flow::Save => match save(args.path(), &mut editor) {
+                    Ok(()) => editor.saved(),
+                    Err(err) => editor.report(err.as_ref()),
+                },
+                Flow::Quit => return Ok(()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn editor() -> Editor {
+        let mut week_plan = WeekPlan::new(Time::new(8, 30).unwrap(), 90, 3).unwrap();
+        week_plan
+            .try_insert(Weekday::Tuesday, Time::new(10, 0).unwrap(), "B".into())
+            .unwrap();
+        Editor::new(week_plan, Locale::default(), "plan.json")
+    }
+
+    fn press(editor: &mut Editor, keys: &str) -> Flow {
+        let mut flow = Flow::Continue;
+        for key in parse_keys(keys.as_bytes()) {
+            flow = editor.handle(key);
+        }
+        flow
+    }
+
+    fn at(editor: &Editor, weekday: Weekday, hour: u8, minute: u8) -> Option<&str> {
+        let time = Time::new(hour, minute).unwrap();
+        editor.week_plan().get(weekday, time).map(String::as_str)
+    }
+
+    #[test]
+    fn keys() {
+        assert_eq!(
+            parse_keys("\x1b[A\x1bOBx\r\x7f\x1b[3~\x1b\x1b[1;5Cè\x03".as_bytes()),
+            [
+                Key::Up,
+                Key::Down,
+                Key::Char('x'),
+                Key::Enter,
+                Key::Backspace,
+                Key::Delete,
+                Key::Esc,
+                Key::Char('è'),
+                Key::Interrupt,
+            ]
+        );
+    }
+
+    #[test]
+    fn editing() {
+        let mut editor = editor();
+
+        press(&mut editor, "\rMath\r");
+        assert_eq!(at(&editor, Weekday::Monday, 8, 30), Some("Math"));
+        press(&mut editor, "++");
+        assert_eq!(at(&editor, Weekday::Monday, 11, 30), Some("Math"));
+        press(&mut editor, "+");
+        assert_eq!(
+            editor.draw(80, 24).lines().nth(7).unwrap(),
+            format!("{RED}Slot is outside of the last hour for the day{RESET}\x1b[K")
+        );
+
+        // Tuesday 08:30 can't grow into the booking below it
+        press(&mut editor, "l\rA\r+");
+        assert!(editor
+            .draw(80, 24)
+            .contains("Slot Tuesday 10:00 already booked"));
+
+        press(&mut editor, "jd");
+        assert_eq!(at(&editor, Weekday::Tuesday, 10, 0), None);
+        press(&mut editor, "uu");
+        assert_eq!(at(&editor, Weekday::Tuesday, 10, 0), Some("B"));
+        assert_eq!(at(&editor, Weekday::Tuesday, 8, 30), None);
+        press(&mut editor, "r");
+        assert_eq!(at(&editor, Weekday::Tuesday, 8, 30), Some("A"));
+
+        // Renaming to nothing frees the slot
+        press(&mut editor, "\r\x7f\r");
+        assert_eq!(at(&editor, Weekday::Tuesday, 8, 30), None);
+        press(&mut editor, "\rC\x1b");
+        assert_eq!(at(&editor, Weekday::Tuesday, 8, 30), None);
+    }
+
+    #[test]
+    fn quitting() {
+        let mut editor = editor();
+        assert_eq!(press(&mut editor, "q"), Flow::Quit);
+
+        assert_eq!(press(&mut editor, "\rA\rq"), Flow::Continue);
+        assert_eq!(press(&mut editor, "jq"), Flow::Continue);
+        assert_eq!(press(&mut editor, "q"), Flow::Quit);
+        assert_eq!(press(&mut editor, "s"), Flow::Save);
+        editor.saved();
+        assert_eq!(press(&mut editor, "q"), Flow::Quit);
+    }
+
+    #[test]
+    fn drawing() {
+        let mut editor = editor().colors(false);
+        press(&mut editor, "\rComputer architectures\r");
+
+        let screen = editor.draw(60, 6);
+        let lines: Vec<&str> = screen.split("\r\n").collect();
+        assert_eq!(lines.len(), 7);
+        assert!(lines[0].starts_with("\x1b[H\x1b[1mWeekly plan — plan.json [+]"));
+        assert!(lines[2].contains("│\x1b[1m Mon  \x1b[0m"));
+        assert!(lines[3].starts_with(" 08:30 │\x1b[7m Com… \x1b[0m│"));
+        assert_eq!(lines[6], format!("{}\x1b[K\x1b[J", truncate(HELP, 60)));
+    }
+}
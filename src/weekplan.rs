@@ -1,22 +1,45 @@
 use core::fmt;
 use std::{collections::HashMap, fmt::Display, str::FromStr};
 
+pub use self::serde::{time_object, TimeObjects};
 use ::serde::{de, Deserialize, Deserializer, Serialize};
 pub use activity::Activity;
-pub use time::Time;
-pub use weekday::Weekday;
+pub use color::{Color, ParseColorError};
+pub use time::{FormattedTime, ParseTimeError, ParseTimeErrorKind, Time};
+pub use weekday::{Days, ParseWeekdayError, Weekday};
+use weekly_planner_core::grid;
 
 mod activity;
+pub mod builder;
+mod color;
+pub mod csv;
+pub mod format;
+pub mod history;
+pub mod locale;
+pub mod migration;
+pub mod render;
+pub mod schema;
 mod serde;
 mod time;
+pub mod validation;
 mod weekday;
 
 #[derive(Debug, Hash, PartialEq, Eq)]
 pub struct Slot(Weekday, Time);
 
+impl Slot {
+    pub fn weekday(&self) -> Weekday {
+        self.0
+    }
+
+    pub fn time(&self) -> Time {
+        self.1
+    }
+}
+
 impl Display for Slot {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?} {}", self.0, self.1.to_string())
+        write!(f, "{} {}", self.0, self.1)
     }
 }
 
@@ -25,7 +48,7 @@ impl Serialize for Slot {
     where
         S: ::serde::Serializer,
     {
-        format!("{:?} {}", self.0, self.1).serialize(serializer)
+        self.to_string().serialize(serializer)
     }
 }
 
@@ -35,19 +58,13 @@ impl<'de> Deserialize<'de> for Slot {
         D: Deserializer<'de>,
     {
         let s = String::deserialize(deserializer)?;
-        let mut parts = s.split_whitespace();
-
-        let weekday = parts
-            .next()
-            .ok_or_else(|| de::Error::custom("Missing weekday"))?;
-        let time = parts.next().ok_or_else(|| {
-            let custom = de::Error::custom("Missing time");
-            custom
-        })?;
+        let (weekday, time) = s
+            .trim()
+            .split_once(char::is_whitespace)
+            .ok_or_else(|| de::Error::custom("Missing time"))?;
 
-        let weekday =
-            Weekday::from_str(weekday).map_err(|_| de::Error::custom("Invalid weekday"))?;
-        let time = Time::from_str(time).map_err(|_| de::Error::custom("Invalid time"))?;
+        let weekday = Weekday::from_str(weekday).map_err(de::Error::custom)?;
+        let time = Time::from_str(time).map_err(de::Error::custom)?;
 
         Ok(Slot(weekday, time))
     }
@@ -59,6 +76,8 @@ pub struct WeekPlan {
     start: Time,
     slot_duration: u16,
     slots: u8,
+    first_weekday: Weekday,
+    colors: HashMap<Activity, Color>,
 }
 
 #[derive(Debug)]
@@ -85,35 +104,132 @@ impl std::error::Error for Error {}
 type Result<T> = std::result::Result<T, Error>;
 
 impl WeekPlan {
+    /// Creates an empty plan. Slots last between a minute and a day, and the
+    /// last one may end at most at [`Time::END_OF_DAY`].
     pub fn new(start: Time, slot_duration: u16, slots: u8) -> Option<Self> {
-        start.try_sum(u16::from(slots) * slot_duration)?;
+        if !grid::fits(start.to_minutes(), slot_duration, slots) {
+            return None;
+        }
 
         Some(WeekPlan {
             plan: HashMap::new(),
             start,
             slot_duration,
             slots,
+            first_weekday: Weekday::Monday,
+            colors: HashMap::new(),
         })
     }
 
+    pub fn slot_duration(&self) -> u16 {
+        self.slot_duration
+    }
+
+    /// Sets the color `activity` is drawn with, overriding the one derived
+    /// from its name.
+    pub fn set_color(&mut self, activity: impl Into<Activity>, color: Color) -> &mut Self {
+        self.colors.insert(activity.into(), color);
+        self
+    }
+
+    /// The color of `activity`: the one set explicitly if any, otherwise one
+    /// derived from its name.
+    pub fn color(&self, activity: &str) -> Color {
+        self.colors
+            .get(activity)
+            .copied()
+            .unwrap_or_else(|| Color::from_name(activity))
+    }
+
+    /// Every booked activity with its total weekly minutes, sorted by name.
+    pub fn activities(&self) -> Vec<(Activity, u32)> {
+        let mut totals: HashMap<&Activity, u32> = HashMap::new();
+        for activity in self.plan.values() {
+            *totals.entry(activity).or_default() += u32::from(self.slot_duration);
+        }
+
+        let mut activities: Vec<_> = totals
+            .into_iter()
+            .map(|(activity, minutes)| (activity.clone(), minutes))
+            .collect();
+        activities.sort();
+        activities
+    }
+
+    /// The day columns start from when rendering the plan. Monday by default.
+    pub fn first_weekday(&self) -> Weekday {
+        self.first_weekday
+    }
+
+    pub fn set_first_weekday(&mut self, weekday: Weekday) -> &mut Self {
+        self.first_weekday = weekday;
+        self
+    }
+
+    /// The days of the week in display order.
+    pub fn weekdays(&self) -> Days {
+        Weekday::week_starting(self.first_weekday)
+    }
+
+    /// Removes every booking, keeping the slots and the colors.
+    pub fn clear(&mut self) -> &mut Self {
+        self.plan.clear();
+        self
+    }
+
+    /// The start of every slot of a day, in order.
+    pub fn slot_times(&self) -> Vec<Time> {
+        (0..self.slots)
+            .map(|num| {
+                self.start
+                    .try_sum(u16::from(num) * self.slot_duration)
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    /// Every booking as `(weekday, slot, activity)`, sorted by weekday from
+    /// Monday and by time.
+    pub fn bookings(&self) -> Vec<(Weekday, Time, &Activity)> {
+        let mut bookings: Vec<_> = self
+            .plan
+            .iter()
+            .map(|(Slot(weekday, time), activity)| (*weekday, *time, activity))
+            .collect();
+        bookings.sort();
+        bookings
+    }
+
+    /// The activity booked in a slot, if any.
+    pub fn get(&self, weekday: Weekday, slot: Time) -> Option<&Activity> {
+        self.plan.get(&Slot(weekday, slot))
+    }
+
+    /// Frees a slot, returning the activity booked in it.
+    pub fn remove(&mut self, weekday: Weekday, slot: Time) -> Option<Activity> {
+        self.plan.remove(&Slot(weekday, slot))
+    }
+
     pub fn is_valid_slot(&self, slot: Time) -> bool {
-        let slot = slot.to_minutes();
+        grid::is_slot_start(
+            self.start.to_minutes(),
+            self.slot_duration,
+            self.slots,
+            slot.to_minutes(),
+        )
+    }
+
+    /// Returns the start of the slot closest to `time`, preferring the earlier
+    /// one on ties.
+    pub fn nearest_slot(&self, time: Time) -> Time {
         let start = self.start.to_minutes();
-        let distance = match slot.checked_sub(start) {
-            Some(val) => val,
-            None => {
-                return false;
-            }
-        };
+        let last = u16::from(self.slots.saturating_sub(1));
+        let duration = self.slot_duration.max(1);
 
-        let slot_index = match u8::try_from(distance / u16::from(self.slot_duration)) {
-            Ok(val) => val,
-            Err(_) => {
-                return false;
-            }
-        };
+        let distance = time.to_minutes().saturating_sub(start);
+        let index = ((distance + (duration - 1) / 2) / duration).min(last);
 
-        distance % u16::from(self.slot_duration) == 0 && slot_index < self.slots
+        self.start.try_sum(index * self.slot_duration).unwrap()
     }
 
     pub fn try_insert(
@@ -161,23 +277,9 @@ impl WeekPlan {
     fn to_table(&self) -> (Vec<Weekday>, Vec<Time>, Vec<Activity>) {
         let default_activity: Activity = "".into();
 
-        let weekdays = vec![
-            Weekday::Monday,
-            Weekday::Tuesday,
-            Weekday::Wednesday,
-            Weekday::Thursday,
-            Weekday::Friday,
-            Weekday::Saturday,
-            Weekday::Sunday,
-        ];
-
-        let times: Vec<Time> = (0..self.slots)
-            .map(|num| {
-                self.start
-                    .try_sum(u16::from(num) * self.slot_duration)
-                    .unwrap()
-            })
-            .collect();
+        let weekdays: Vec<Weekday> = self.weekdays().collect();
+
+        let times = self.slot_times();
 
         let table: Vec<Activity> = weekdays
             .iter()
@@ -201,127 +303,101 @@ impl WeekPlan {
         (weekdays, times, table)
     }
 
+    /// Renders the plan as an English HTML page, see [`render::Html`] for more
+    /// options.
     pub fn to_html(&self) -> String {
-        let (weekdays, times, table) = self.to_table();
-
-        let css = r#"
-            body {
-                font-family: Arial, sans-serif;
-                display: flex;
-                justify-content: center;
-                align-items: center;
-                min-height: 100vh;
-                margin: 0;
-                background-color: #f0f0f0;
-            }
-            .schedule-table {
-                border-collapse: collapse;
-                box-shadow: 0 0 20px rgba(0, 0, 0, 0.1);
-                background-color: white;
-            }
-            .schedule-table th,
-            .schedule-table td {
-                padding: 12px 15px;
-                text-align: center;
-            }
-            .schedule-table th {
-                background-color: #009879;
-                color: white;
-                text-transform: uppercase;
-                font-weight: bold;
-            }
-            .schedule-table td {
-                border-bottom: 1px solid #dddddd;
-            }
-            .schedule-table tr:nth-child(even) {
-                background-color: #f3f3f3;
-            }
-            .schedule-table tr:last-of-type {
-                border-bottom: 2px solid #009879;
-            }
-            .schedule-table tr:hover {
-                background-color: #f5f5f5;
-                transition: background-color 0.3s ease;
-            }
-            .header-row th:first-child {
-                background-color: #007965;
-            }
-        "#;
-
-        let html_start = format!(
-            r#"<!DOCTYPE html>
-    <html lang="en">
-    <head>
-        <meta charset="UTF-8">
-        <meta name="viewport" content="width=device-width, initial-scale=1.0">
-        <title>Styled Table</title>
-        <style>
-        {}
-        </style>
-    </head>
-    <body>
-        <table class="schedule-table">
-            <tr class="header-row">
-                <th></th>"#,
-            css
-        );
+        render::Html::new().render_to_string(self)
+    }
+}
 
-        let html_end = r#"    </table>
-    </body>
-    </html>"#;
+#[cfg(test)]
+mod test {
+    use super::*;
 
-        let mut html = String::new();
-        html.push_str(&html_start);
+    #[test]
+    fn test_valid_slot() {
+        let planner = WeekPlan::new(Time::new(8, 30).unwrap(), 90, 7).unwrap();
+        assert!(planner.is_valid_slot(Time::new(14, 30).unwrap()));
+        assert!(!planner.is_valid_slot(Time::new(14, 00).unwrap()));
+    }
 
-        // Add weekday headers
-        for day in &weekdays {
-            html.push_str(&format!("            <th>{:?}</th>\n", day));
-        }
-        html.push_str("        </tr>\n");
+    #[test]
+    fn test_zero_slot_duration() {
+        let start = Time::new(8, 30).unwrap();
+        assert!(WeekPlan::new(start, 0, 7).is_none());
+        assert!(WeekPlan::new(start, 0, 0).is_none());
+        assert!(WeekPlan::new(start, 1, 0).is_some());
+    }
 
-        // Add table rows
-        for (i, time) in times.iter().enumerate() {
-            html.push_str("        <tr>\n");
-            html.push_str(&format!("            <th>{}</th>\n", time.to_string(),));
+    #[test]
+    fn test_day_long_slot_duration() {
+        let start = Time::new(0, 0).unwrap();
+        assert!(WeekPlan::new(start, Time::MINUTES_PER_DAY, 1).is_some());
+        assert!(WeekPlan::new(start, Time::MINUTES_PER_DAY + 1, 0).is_none());
+    }
 
-            for j in 0..weekdays.len() {
-                let activity = &table[i + j * times.len()];
-                html.push_str(&format!("            <td>{}</td>\n", activity));
-            }
+    #[test]
+    fn test_plan_ending_at_midnight() {
+        let planner = WeekPlan::new(Time::new(13, 30).unwrap(), 90, 7).unwrap();
+        assert!(planner.is_valid_slot(Time::new(22, 30).unwrap()));
+        assert!(!planner.is_valid_slot(Time::END_OF_DAY));
 
-            html.push_str("        </tr>\n");
-        }
+        assert!(WeekPlan::new(Time::new(13, 31).unwrap(), 90, 7).is_none());
+    }
 
-        html.push_str(html_end);
-        html
+    #[test]
+    fn test_activities() {
+        let mut planner = WeekPlan::new(Time::new(8, 30).unwrap(), 90, 7).unwrap();
+        planner
+            .try_insert_range(Weekday::Monday, (Time::new(8, 30).unwrap(), 2), "B".into())
+            .unwrap()
+            .try_insert(Weekday::Friday, Time::new(8, 30).unwrap(), "A".into())
+            .unwrap()
+            .set_color("A", Color::BLACK);
+
+        assert_eq!(
+            planner.activities(),
+            vec![("A".to_owned(), 90), ("B".to_owned(), 180)]
+        );
+        assert_eq!(planner.color("A"), Color::BLACK);
+        assert_eq!(planner.color("B"), Color::from_name("B"));
     }
-}
 
-#[macro_export]
-macro_rules! poli_plan {
-    ($start:expr, $duration:expr, $days:expr, $($day:expr => $time:expr , $length:expr , $name:expr),* $(,)?) => {{
-        let mut w = WeekPlan::new($start, $duration, $days).unwrap();
-        $(
-            let weekday = Weekday::from_str($day).unwrap();
-            let time = Time::from_str($time).unwrap();
-            w.try_insert_range(
-                weekday,
-                (time, $length),
-                $name.to_owned(),
-            ).unwrap();
-        )*
-        w
-    }};
-}
+    #[test]
+    fn test_bookings() {
+        let mut planner = WeekPlan::new(Time::new(8, 30).unwrap(), 90, 7).unwrap();
+        planner
+            .try_insert(Weekday::Tuesday, Time::new(8, 30).unwrap(), "B".into())
+            .unwrap()
+            .try_insert_range(Weekday::Monday, (Time::new(10, 0).unwrap(), 2), "A".into())
+            .unwrap();
 
-#[cfg(test)]
-mod test {
-    use super::*;
+        let time = |hour, minute| Time::new(hour, minute).unwrap();
+        let (a, b) = ("A".to_owned(), "B".to_owned());
+        assert_eq!(
+            planner.bookings(),
+            [
+                (Weekday::Monday, time(10, 0), &a),
+                (Weekday::Monday, time(11, 30), &a),
+                (Weekday::Tuesday, time(8, 30), &b),
+            ]
+        );
+
+        assert_eq!(planner.get(Weekday::Monday, time(10, 0)), Some(&a));
+        assert_eq!(planner.remove(Weekday::Monday, time(10, 0)), Some(a));
+        assert_eq!(planner.get(Weekday::Monday, time(10, 0)), None);
+        assert_eq!(planner.remove(Weekday::Monday, time(10, 0)), None);
+        assert_eq!(planner.bookings().len(), 2);
+    }
 
     #[test]
-    fn test_valid_slot() {
+    fn test_nearest_slot() {
         let planner = WeekPlan::new(Time::new(8, 30).unwrap(), 90, 7).unwrap();
-        assert!(planner.is_valid_slot(Time::new(14, 30).unwrap()));
-        assert!(!planner.is_valid_slot(Time::new(14, 00).unwrap()));
+        let nearest = |hour, minute| planner.nearest_slot(Time::new(hour, minute).unwrap());
+
+        assert_eq!(nearest(14, 0), Time::new(14, 30).unwrap());
+        assert_eq!(nearest(13, 45), Time::new(13, 0).unwrap());
+        assert_eq!(nearest(6, 0), Time::new(8, 30).unwrap());
+        assert_eq!(nearest(23, 0), Time::new(17, 30).unwrap());
     }
 }
@@ -0,0 +1,465 @@
+//! Building plans in code without panicking.
+//!
+//! ```
+//! use weekly_planner::WeekPlan;
+//!
+//! let week_plan = WeekPlan::builder("8:30", 90, 7)
+//!     .book("Monday", "10:00", 2, "Computer architectures")
+//!     .book("Tuesday", "8:30", 2, "Electronics")
+//!     .color("Electronics", "#ffcc00")
+//!     .build()
+//!     .unwrap();
+//! assert_eq!(week_plan.bookings().len(), 4);
+//!
+//! let err = WeekPlan::builder("8:30", 90, 7)
+//!     .book("Moonday", "10:00", 1, "A")
+//!     .book("Monday", "10:15", 1, "B")
+//!     .build()
+//!     .unwrap_err();
+//! assert_eq!(err.problems().len(), 2);
+//! ```
+//!
+//! [`try_poli_plan!`](crate::try_poli_plan) does the same with less typing,
+//! and [`checked_poli_plan!`](crate::checked_poli_plan) checks plans written
+//! with literals while compiling:
+//!
+//! ```compile_fail
+//! let week_plan = weekly_planner::checked_poli_plan! {
+//!     "8:30", 90, 7,
+//!     "Monday" => "10:15", 1, "A",
+//! };
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::{
+    Activity, Color, Error, ParseColorError, ParseTimeError, ParseWeekdayError, Time, WeekPlan,
+    Weekday,
+};
+
+/// A value given to [`Builder`], either as is or as text to parse.
+pub trait Field<T>: fmt::Display {
+    fn parse_field(&self) -> Result<T, ProblemKind>;
+}
+
+macro_rules! impl_field {
+    ($type:ty, $kind:ident) => {
+        impl Field<$type> for $type {
+            fn parse_field(&self) -> Result<$type, ProblemKind> {
+                Ok(*self)
+            }
+        }
+
+        impl Field<$type> for &str {
+            fn parse_field(&self) -> Result<$type, ProblemKind> {
+                <$type>::from_str(self).map_err(ProblemKind::$kind)
+            }
+        }
+
+        impl Field<$type> for String {
+            fn parse_field(&self) -> Result<$type, ProblemKind> {
+                self.as_str().parse_field()
+            }
+        }
+    };
+}
+
+impl_field!(Time, Time);
+impl_field!(Weekday, Weekday);
+impl_field!(Color, Color);
+
+/// The call of [`Builder`] a problem comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Part {
+    /// The start, slot duration and number of slots.
+    Layout,
+    FirstWeekday,
+    /// A color, counted from 1 in the order they were given, for the
+    /// activity.
+    Color(usize, Activity),
+    /// A booking, counted from 1 in the order they were given, as
+    /// `weekday time` written by the caller.
+    Booking(usize, String),
+}
+
+#[derive(Debug)]
+pub enum ProblemKind {
+    Weekday(ParseWeekdayError),
+    Time(ParseTimeError),
+    Color(ParseColorError),
+    /// The slots are empty or don't fit in a day.
+    Layout,
+    Booking(Error),
+}
+
+/// A problem with one of the calls made to a [`Builder`].
+#[derive(Debug)]
+pub struct Problem {
+    pub part: Part,
+    pub kind: ProblemKind,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.part {
+            Part::Layout => write!(f, "layout: ")?,
+            Part::FirstWeekday => write!(f, "first weekday: ")?,
+            Part::Color(index, activity) => write!(f, "color {index} ({activity}): ")?,
+            Part::Booking(index, slot) => write!(f, "booking {index} ({slot}): ")?,
+        }
+        match &self.kind {
+            ProblemKind::Weekday(err) => write!(f, "{err}"),
+            ProblemKind::Time(err) => write!(f, "{err}"),
+            ProblemKind::Color(err) => write!(f, "{err}"),
+            ProblemKind::Layout => {
+                write!(
+                    f,
+                    "slots must last between a minute and a day and end by midnight"
+                )
+            }
+            ProblemKind::Booking(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+/// Every problem found by [`Builder::build`], in the order of the calls.
+#[derive(Debug)]
+pub struct BuildError {
+    problems: Vec<Problem>,
+}
+
+impl BuildError {
+    pub fn problems(&self) -> &[Problem] {
+        &self.problems
+    }
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, problem) in self.problems.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{problem}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+#[derive(Debug)]
+struct Booking {
+    slot: String,
+    weekday: Result<Weekday, ProblemKind>,
+    time: Result<Time, ProblemKind>,
+    len: u8,
+    activity: Activity,
+}
+
+/// Builds a [`WeekPlan`], collecting every problem instead of stopping at the
+/// first one. Weekdays, times and colors can be given as values or as text.
+#[derive(Debug)]
+pub struct Builder {
+    start: Result<Time, ProblemKind>,
+    slot_duration: u16,
+    slots: u8,
+    first_weekday: Option<Result<Weekday, ProblemKind>>,
+    colors: Vec<(Activity, Result<Color, ProblemKind>)>,
+    bookings: Vec<Booking>,
+}
+
+impl Builder {
+    pub fn new(start: impl Field<Time>, slot_duration: u16, slots: u8) -> Self {
+        Builder {
+            start: start.parse_field(),
+            slot_duration,
+            slots,
+            first_weekday: None,
+            colors: Vec::new(),
+            bookings: Vec::new(),
+        }
+    }
+
+    pub fn first_weekday(mut self, weekday: impl Field<Weekday>) -> Self {
+        self.first_weekday = Some(weekday.parse_field());
+        self
+    }
+
+    /// Sets the color of an activity, see [`WeekPlan::set_color`].
+    pub fn color(mut self, activity: impl Into<Activity>, color: impl Field<Color>) -> Self {
+        self.colors.push((activity.into(), color.parse_field()));
+        self
+    }
+
+    /// Books `len` consecutive slots from `time`, see
+    /// [`WeekPlan::try_insert_range`]. Bookings are all or nothing: a booking
+    /// running into a booked slot books none of its slots.
+    pub fn book(
+        mut self,
+        weekday: impl Field<Weekday>,
+        time: impl Field<Time>,
+        len: u8,
+        activity: impl Into<Activity>,
+    ) -> Self {
+        self.bookings.push(Booking {
+            slot: format!("{weekday} {time}"),
+            weekday: weekday.parse_field(),
+            time: time.parse_field(),
+            len,
+            activity: activity.into(),
+        });
+        self
+    }
+
+    pub fn build(self) -> Result<WeekPlan, BuildError> {
+        let mut problems = Vec::new();
+        let mut problem = |part: Part, kind: ProblemKind| problems.push(Problem { part, kind });
+
+        let mut week_plan = match self.start {
+            Ok(start) => {
+                let week_plan = WeekPlan::new(start, self.slot_duration, self.slots);
+                if week_plan.is_none() {
+                    problem(Part::Layout, ProblemKind::Layout);
+                }
+                week_plan
+            }
+            Err(kind) => {
+                problem(Part::Layout, kind);
+                None
+            }
+        };
+
+        match self.first_weekday {
+            Some(Ok(weekday)) => {
+                if let Some(week_plan) = &mut week_plan {
+                    week_plan.set_first_weekday(weekday);
+                }
+            }
+            Some(Err(kind)) => problem(Part::FirstWeekday, kind),
+            None => {}
+        }
+
+        for (i, (activity, color)) in self.colors.into_iter().enumerate() {
+            match color {
+                Ok(color) => {
+                    if let Some(week_plan) = &mut week_plan {
+                        week_plan.set_color(activity, color);
+                    }
+                }
+                Err(kind) => problem(Part::Color(i + 1, activity), kind),
+            }
+        }
+
+        for (i, booking) in self.bookings.into_iter().enumerate() {
+            let part = || Part::Booking(i + 1, booking.slot.clone());
+            let (weekday, time) = match (booking.weekday, booking.time) {
+                (Ok(weekday), Ok(time)) => (weekday, time),
+                (weekday, time) => {
+                    for kind in [weekday.err(), time.err()].into_iter().flatten() {
+                        problem(part(), kind);
+                    }
+                    continue;
+                }
+            };
+
+            if let Some(week_plan) = &mut week_plan {
+                if let Err(err) = book(week_plan, weekday, time, booking.len, booking.activity) {
+                    problem(part(), ProblemKind::Booking(err));
+                }
+            }
+        }
+
+        match week_plan {
+            Some(week_plan) if problems.is_empty() => Ok(week_plan),
+            _ => Err(BuildError { problems }),
+        }
+    }
+}
+
+/// Books consecutive slots, or none of them if one can't be booked.
+fn book(
+    week_plan: &mut WeekPlan,
+    weekday: Weekday,
+    start: Time,
+    len: u8,
+    activity: Activity,
+) -> Result<(), Error> {
+    let mut times = Vec::with_capacity(usize::from(len));
+    for i in 0..len {
+        let time = start
+            .try_sum(u16::from(i) * week_plan.slot_duration)
+            .ok_or(Error::OutOfBounds)?;
+        if !week_plan.is_valid_slot(time) {
+            return Err(Error::InvalidSlot(time));
+        }
+        if week_plan.get(weekday, time).is_some() {
+            return Err(Error::AlreadyBooked(super::Slot(weekday, time)));
+        }
+        times.push(time);
+    }
+
+    for time in times {
+        week_plan.try_insert(weekday, time, activity.clone())?;
+    }
+    Ok(())
+}
+
+impl WeekPlan {
+    /// Starts building a plan, see [`Builder`].
+    pub fn builder(start: impl Field<Time>, slot_duration: u16, slots: u8) -> Builder {
+        Builder::new(start, slot_duration, slots)
+    }
+}
+
+/// Builds a [`WeekPlan`](crate::WeekPlan) from its start, slot duration and
+/// number of slots, followed by bookings as `weekday => time, slots,
+/// activity`, returning a [`BuildError`] listing every invalid entry.
+///
+/// ```
+/// use weekly_planner::try_poli_plan;
+///
+/// let week_plan = try_poli_plan! {
+///     "8:30", 90, 7,
+///     "Monday" => "10:00", 2, "Computer architectures",
+///     "Tuesday" => "8:30", 2, "Electronics",
+/// };
+/// assert!(week_plan.is_ok());
+/// ```
+#[macro_export]
+macro_rules! try_poli_plan {
+    ($start:expr, $duration:expr, $days:expr $(, $day:expr => $time:expr, $length:expr, $name:expr)* $(,)?) => {
+        $crate::weekplan::builder::Builder::new($start, $duration, $days)
+            $(.book($day, $time, $length, $name))*
+            .build()
+    };
+}
+
+/// Like [`try_poli_plan!`](crate::try_poli_plan), panicking with every
+/// problem found if the plan is invalid.
+#[macro_export]
+macro_rules! poli_plan {
+    ($($plan:tt)*) => {
+        match $crate::try_poli_plan!($($plan)*) {
+            ::core::result::Result::Ok(week_plan) => week_plan,
+            ::core::result::Result::Err(err) => ::core::panic!("invalid plan:\n{}", err),
+        }
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn build() {
+        let week_plan = Builder::new(Time::new(8, 30).unwrap(), 90, 4)
+            .first_weekday(Weekday::Sunday)
+            .color("A", "#fff")
+            .book("Monday", "8:30", 2, "A")
+            .book(
+                Weekday::Tuesday,
+                Time::new(13, 0).unwrap(),
+                1,
+                "B".to_owned(),
+            )
+            .build()
+            .unwrap();
+
+        let mut expected = WeekPlan::new(Time::new(8, 30).unwrap(), 90, 4).unwrap();
+        expected
+            .try_insert_range(Weekday::Monday, (Time::new(8, 30).unwrap(), 2), "A".into())
+            .unwrap()
+            .try_insert(Weekday::Tuesday, Time::new(13, 0).unwrap(), "B".into())
+            .unwrap()
+            .set_first_weekday(Weekday::Sunday)
+            .set_color("A", Color::WHITE);
+        assert_eq!(week_plan, expected);
+    }
+
+    #[test]
+    fn problems() {
+        let err = WeekPlan::builder("8:30", 90, 4)
+            .first_weekday("Someday")
+            .color("A", "white")
+            .book("Monday", "8:30", 2, "A")
+            .book("Moonday", "25:00", 1, "B")
+            .book("Monday", "10:00", 2, "C")
+            .book("Friday", "13:00", 2, "D")
+            .book("Friday", "13:00", 1, "E")
+            .build()
+            .unwrap_err();
+
+        let problems: Vec<String> = err.problems().iter().map(Problem::to_string).collect();
+        assert_eq!(problems.len(), 6);
+        assert!(problems[0].starts_with("first weekday: "));
+        assert!(problems[1].starts_with("color 1 (A): invalid color \"white\""));
+        assert!(problems[2].starts_with("booking 2 (Moonday 25:00): "));
+        assert!(problems[3].starts_with("booking 2 (Moonday 25:00): "));
+        assert_eq!(
+            problems[4],
+            "booking 3 (Monday 10:00): Slot Monday 10:00 already booked"
+        );
+        assert_eq!(problems[5], "booking 4 (Friday 13:00): Invalid slot 14:30");
+        // Booking 4 didn't book Friday 13:00, so booking 5 could
+        assert_eq!(err.to_string().lines().count(), 6);
+    }
+
+    #[test]
+    fn layout() {
+        let err = WeekPlan::builder("8:30", 0, 4).build().unwrap_err();
+        assert!(matches!(
+            err.problems(),
+            [Problem {
+                part: Part::Layout,
+                kind: ProblemKind::Layout
+            }]
+        ));
+
+        // Bookings are still checked for typos without a layout
+        let err = WeekPlan::builder("8:3O", 90, 4)
+            .book("Monday", "8:30", 1, "A")
+            .book("Mun", "8:30", 1, "A")
+            .build()
+            .unwrap_err();
+        assert_eq!(err.problems().len(), 2);
+        assert_eq!(err.problems()[1].part, Part::Booking(2, "Mun 8:30".into()));
+    }
+
+    /// The macros only use `$crate` paths, so they work without imports.
+    mod hygiene {
+        #[test]
+        fn macros() {
+            let week_plan = crate::try_poli_plan! {
+                "8:30", 90, 2,
+                "Monday" => "8:30", 2, "A",
+                "Tuesday" => "10:00", 1, "B",
+            }
+            .unwrap();
+            assert_eq!(week_plan.bookings().len(), 3);
+
+            let err = crate::try_poli_plan! { "8:30", 90, 2, "Monday" => "9:00", 1, "A" };
+            assert_eq!(
+                err.unwrap_err().to_string(),
+                "booking 1 (Monday 9:00): Invalid slot 09:00"
+            );
+
+            let week_plan = crate::poli_plan! { "8:30", 90, 2 };
+            assert!(week_plan.bookings().is_empty());
+        }
+
+        #[test]
+        #[should_panic(
+            expected = "invalid plan:\nbooking 2 (Monday 8:30): Slot Monday 08:30 already booked"
+        )]
+        fn poli_plan_panics() {
+            crate::poli_plan! {
+                "8:30", 90, 2,
+                "Monday" => "8:30", 1, "A",
+                "Monday" => "8:30", 1, "B",
+            };
+        }
+    }
+}
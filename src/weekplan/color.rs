@@ -0,0 +1,177 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// An sRGB color, written as `#rrggbb`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseColorError(String);
+
+impl fmt::Display for ParseColorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid color {:?}, expected #rrggbb or #rgb", self.0)
+    }
+}
+
+impl std::error::Error for ParseColorError {}
+
+impl Color {
+    pub const BLACK: Color = Color::rgb(0, 0, 0);
+    pub const WHITE: Color = Color::rgb(255, 255, 255);
+
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Color { r, g, b }
+    }
+
+    /// A pastel color derived from `name`. The same name always gets the same
+    /// color, on every platform and release.
+    pub fn from_name(name: &str) -> Self {
+        // FNV-1a, since the std hashers are not guaranteed to be stable.
+        let hash = name.bytes().fold(0x811c9dc5_u32, |hash, byte| {
+            (hash ^ u32::from(byte)).wrapping_mul(0x01000193)
+        });
+
+        let hue = f64::from(hash % 360);
+        Color::from_hsl(hue, 0.55, 0.75)
+    }
+
+    /// Builds a color from a hue in degrees and a saturation and lightness
+    /// between 0 and 1.
+    pub fn from_hsl(hue: f64, saturation: f64, lightness: f64) -> Self {
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        let sector = hue.rem_euclid(360.0) / 60.0;
+        let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+
+        let (r, g, b) = match sector as u8 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+
+        let m = lightness - chroma / 2.0;
+        let channel = |value: f64| ((value + m) * 255.0).round() as u8;
+        Color::rgb(channel(r), channel(g), channel(b))
+    }
+
+    /// The relative luminance of the color, between 0 and 1.
+    pub fn luminance(&self) -> f64 {
+        let linear = |channel: u8| {
+            let c = f64::from(channel) / 255.0;
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+
+        0.2126 * linear(self.r) + 0.7152 * linear(self.g) + 0.0722 * linear(self.b)
+    }
+
+    /// Black or white, whichever is more readable over this color.
+    pub fn contrasting_text(&self) -> Color {
+        if self.luminance() > 0.179 {
+            Color::BLACK
+        } else {
+            Color::WHITE
+        }
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
+impl FromStr for Color {
+    type Err = ParseColorError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseColorError(s.to_owned());
+        let hex = s.trim().strip_prefix('#').ok_or_else(err)?;
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(err());
+        }
+
+        let channel = |digits: &str| u8::from_str_radix(digits, 16).map_err(|_| err());
+        match hex.len() {
+            6 => Ok(Color::rgb(
+                channel(&hex[0..2])?,
+                channel(&hex[2..4])?,
+                channel(&hex[4..6])?,
+            )),
+            3 => {
+                let short = |i: usize| channel(&hex[i..i + 1]).map(|c| c * 17);
+                Ok(Color::rgb(short(0)?, short(1)?, short(2)?))
+            }
+            _ => Err(err()),
+        }
+    }
+}
+
+impl Serialize for Color {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        Color::from_str(&s).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!("#009879".parse(), Ok(Color::rgb(0x00, 0x98, 0x79)));
+        assert_eq!("#fA0".parse(), Ok(Color::rgb(0xff, 0xaa, 0x00)));
+        assert!("009879".parse::<Color>().is_err());
+        assert!("#00987".parse::<Color>().is_err());
+        assert!("#+12345".parse::<Color>().is_err());
+        assert_eq!(Color::rgb(0, 152, 121).to_string(), "#009879");
+    }
+
+    #[test]
+    fn from_name() {
+        assert_eq!(
+            Color::from_name("Electronics"),
+            Color::from_name("Electronics")
+        );
+        assert_ne!(
+            Color::from_name("Electronics"),
+            Color::from_name("Simulation")
+        );
+        assert_eq!(
+            Color::from_name("Electronics").contrasting_text(),
+            Color::BLACK
+        );
+    }
+
+    #[test]
+    fn from_hsl() {
+        assert_eq!(Color::from_hsl(0.0, 1.0, 0.5), Color::rgb(255, 0, 0));
+        assert_eq!(Color::from_hsl(120.0, 1.0, 0.5), Color::rgb(0, 255, 0));
+        assert_eq!(Color::from_hsl(240.0, 1.0, 0.5), Color::rgb(0, 0, 255));
+        assert_eq!(Color::from_hsl(0.0, 0.0, 1.0), Color::WHITE);
+    }
+}
@@ -0,0 +1,466 @@
+//! Spreadsheet exchange: CSV and TSV export of a plan, and import of its
+//! bookings.
+//!
+//! Bookings are exchanged one per row, with a header naming the columns:
+//!
+//! ```text
+//! weekday,start,end,slots,activity,color
+//! Monday,08:30,11:30,2,Computer architectures,
+//! Tuesday,11:30,13:00,1,Electronics,#ffcc00
+//! ```
+//!
+//! `color` is the color set for the activity, if any.
+
+use std::fmt;
+use std::io::{self, Write};
+
+use super::render::{merge_cells, Render};
+use super::{
+    Color, Error, ParseColorError, ParseTimeError, ParseWeekdayError, Time, WeekPlan, Weekday,
+};
+
+const COLUMNS: [&str; 6] = ["weekday", "start", "end", "slots", "activity", "color"];
+
+/// Exports a plan as delimiter-separated values and imports bookings back.
+#[derive(Debug, Clone)]
+pub struct Csv {
+    delimiter: char,
+    grid: bool,
+}
+
+impl Default for Csv {
+    fn default() -> Self {
+        Csv {
+            delimiter: ',',
+            grid: false,
+        }
+    }
+}
+
+impl Csv {
+    /// Comma-separated values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Tab-separated values.
+    pub fn tsv() -> Self {
+        Self::new().delimiter('\t')
+    }
+
+    pub fn delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Whether to export the timetable as laid out in the HTML page, a row
+    /// per slot and a column per day, instead of a row per booking. Grids
+    /// can't be imported back.
+    pub fn grid(mut self, grid: bool) -> Self {
+        self.grid = grid;
+        self
+    }
+
+    pub fn render_to_string(&self, week_plan: &WeekPlan) -> String {
+        let (weekdays, times, table) = week_plan.to_table();
+        let mut out = String::new();
+
+        if self.grid {
+            let header: Vec<String> = std::iter::once(String::new())
+                .chain(weekdays.iter().map(Weekday::to_string))
+                .collect();
+            self.write_row(&mut out, &header);
+
+            for (slot, time) in times.iter().enumerate() {
+                let row: Vec<String> = std::iter::once(time.to_string())
+                    .chain((0..weekdays.len()).map(|day| table[day * times.len() + slot].clone()))
+                    .collect();
+                self.write_row(&mut out, &row);
+            }
+            return out;
+        }
+
+        self.write_row(&mut out, &COLUMNS.map(str::to_owned));
+
+        // Bookings are listed by weekday from Monday, whatever the first
+        // weekday of the plan.
+        let mut cells = merge_cells(&table, times.len());
+        cells.retain(|cell| !cell.activity.is_empty());
+        cells.sort_by_key(|cell| (weekdays[cell.day], cell.slot));
+
+        for cell in cells {
+            let (from, to) = cell.interval(&times, week_plan.slot_duration);
+            let color = week_plan
+                .colors
+                .get(cell.activity)
+                .map(Color::to_string)
+                .unwrap_or_default();
+
+            self.write_row(
+                &mut out,
+                &[
+                    weekdays[cell.day].to_string(),
+                    from.to_string(),
+                    to.to_string(),
+                    cell.span.to_string(),
+                    cell.activity.clone(),
+                    color,
+                ],
+            );
+        }
+
+        out
+    }
+
+    fn write_row(&self, out: &mut String, fields: &[String]) {
+        let fields: Vec<String> = fields.iter().map(|field| self.quote(field)).collect();
+        out.push_str(&fields.join(&self.delimiter.to_string()));
+        out.push_str("\r\n");
+    }
+
+    /// Quotes a field if it contains the delimiter, a quote or a line break.
+    fn quote(&self, field: &str) -> String {
+        if field.contains([self.delimiter, '"', '\n', '\r']) {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            field.to_owned()
+        }
+    }
+
+    /// Books in `week_plan` every row of `input`, which must start with a
+    /// header naming the columns. `weekday`, `start` and `activity` are
+    /// required, along with `slots` or `end`; `color` is optional. Columns
+    /// may come in any order and unknown ones are ignored.
+    ///
+    /// Returns the number of bookings. Rows are booked one at a time, so on
+    /// error `week_plan` holds the rows before the failing one.
+    pub fn import(&self, week_plan: &mut WeekPlan, input: &str) -> Result<usize, ImportError> {
+        let mut records = self.records(input)?.into_iter();
+        let (_, header) = records.next().ok_or(ImportError {
+            row: 1,
+            kind: ImportErrorKind::MissingHeader,
+        })?;
+
+        let position = |name: &str| {
+            header
+                .iter()
+                .position(|column| column.trim().eq_ignore_ascii_case(name))
+        };
+        let [weekday, start, end, slots, activity, color] = COLUMNS.map(position);
+        let missing = |column| ImportError {
+            row: 1,
+            kind: ImportErrorKind::MissingColumn(column),
+        };
+        let weekday = weekday.ok_or_else(|| missing("weekday"))?;
+        let start = start.ok_or_else(|| missing("start"))?;
+        let activity = activity.ok_or_else(|| missing("activity"))?;
+        if end.is_none() && slots.is_none() {
+            return Err(missing("slots"));
+        }
+
+        let mut count = 0;
+        for (row, fields) in records {
+            if fields.iter().all(|field| field.trim().is_empty()) {
+                continue;
+            }
+
+            let err = |kind| ImportError { row, kind };
+            let field = |index: usize| fields.get(index).map_or("", |field| field.trim());
+            let optional =
+                |index: Option<usize>| index.map(field).filter(|field| !field.is_empty());
+
+            let day: Weekday = field(weekday)
+                .parse()
+                .map_err(|e| err(ImportErrorKind::Weekday(e)))?;
+            let from: Time = field(start)
+                .parse()
+                .map_err(|e| err(ImportErrorKind::Time(e)))?;
+            let to = optional(end)
+                .map(|end| end.parse::<Time>())
+                .transpose()
+                .map_err(|e| err(ImportErrorKind::Time(e)))?;
+            let name = field(activity);
+            if name.is_empty() {
+                return Err(err(ImportErrorKind::MissingActivity));
+            }
+
+            let duration = week_plan.slot_duration;
+            let len = match (optional(slots), to) {
+                (Some(slots), to) => {
+                    let len = slots
+                        .parse::<u8>()
+                        .ok()
+                        .filter(|&len| len > 0)
+                        .ok_or_else(|| err(ImportErrorKind::Slots(slots.to_owned())))?;
+                    let minutes = u16::from(len)
+                        .checked_mul(duration)
+                        .ok_or_else(|| err(ImportErrorKind::Slots(slots.to_owned())))?;
+                    let expected = from.try_sum(minutes);
+                    if let Some(to) = to.filter(|&to| Some(to) != expected) {
+                        return Err(err(ImportErrorKind::EndMismatch(to)));
+                    }
+                    len
+                }
+                (None, Some(to)) => from
+                    .minutes_until(to)
+                    .filter(|&minutes| minutes > 0 && minutes % duration == 0)
+                    .and_then(|minutes| u8::try_from(minutes / duration).ok())
+                    .ok_or_else(|| err(ImportErrorKind::EndMismatch(to)))?,
+                (None, None) => return Err(err(ImportErrorKind::Slots(String::new()))),
+            };
+
+            if let Some(color) = optional(color) {
+                let color = color.parse().map_err(|e| err(ImportErrorKind::Color(e)))?;
+                week_plan.set_color(name, color);
+            }
+
+            week_plan
+                .try_insert_range(day, (from, len), name.to_owned())
+                .map_err(|e| err(ImportErrorKind::Booking(e)))?;
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    /// Splits `input` into records, each with the line it starts on.
+    fn records(&self, input: &str) -> Result<Vec<(usize, Vec<String>)>, ImportError> {
+        let mut records = Vec::new();
+        let mut fields = Vec::new();
+        let mut field = String::new();
+        let mut quoted = false;
+        let mut line = 1;
+        let mut start = 1;
+        let mut chars = input
+            .strip_prefix('\u{feff}')
+            .unwrap_or(input)
+            .chars()
+            .peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '"' if quoted => {
+                    if chars.next_if_eq(&'"').is_some() {
+                        field.push('"');
+                    } else {
+                        quoted = false;
+                    }
+                }
+                '"' if field.is_empty() => quoted = true,
+                '\n' if quoted => {
+                    field.push(c);
+                    line += 1;
+                }
+                '\r' | '\n' if !quoted => {
+                    if c == '\r' {
+                        chars.next_if_eq(&'\n');
+                    }
+                    fields.push(std::mem::take(&mut field));
+                    records.push((start, std::mem::take(&mut fields)));
+                    line += 1;
+                    start = line;
+                }
+                c if c == self.delimiter && !quoted => fields.push(std::mem::take(&mut field)),
+                c => field.push(c),
+            }
+        }
+
+        if quoted {
+            return Err(ImportError {
+                row: start,
+                kind: ImportErrorKind::UnterminatedQuote,
+            });
+        }
+        if !field.is_empty() || !fields.is_empty() {
+            fields.push(field);
+            records.push((start, fields));
+        }
+
+        Ok(records)
+    }
+}
+
+impl Render for Csv {
+    fn render(&self, week_plan: &WeekPlan, out: &mut dyn Write) -> io::Result<()> {
+        out.write_all(self.render_to_string(week_plan).as_bytes())
+    }
+}
+
+/// A row of a spreadsheet that could not be imported.
+#[derive(Debug)]
+pub struct ImportError {
+    /// The line the row starts on, the header being line 1.
+    pub row: usize,
+    pub kind: ImportErrorKind,
+}
+
+#[derive(Debug)]
+pub enum ImportErrorKind {
+    MissingHeader,
+    MissingColumn(&'static str),
+    UnterminatedQuote,
+    Weekday(ParseWeekdayError),
+    Time(ParseTimeError),
+    Slots(String),
+    EndMismatch(Time),
+    MissingActivity,
+    Color(ParseColorError),
+    Booking(Error),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "row {}: ", self.row)?;
+        match &self.kind {
+            ImportErrorKind::MissingHeader => write!(f, "missing header"),
+            ImportErrorKind::MissingColumn(column) => write!(f, "missing column `{column}`"),
+            ImportErrorKind::UnterminatedQuote => write!(f, "unterminated quoted field"),
+            ImportErrorKind::Weekday(err) => write!(f, "{err}"),
+            ImportErrorKind::Time(err) => write!(f, "{err}"),
+            ImportErrorKind::Slots(slots) => write!(f, "invalid number of slots {slots:?}"),
+            ImportErrorKind::EndMismatch(end) => {
+                write!(f, "end {end} doesn't fall at the end of a slot")
+            }
+            ImportErrorKind::MissingActivity => write!(f, "missing activity"),
+            ImportErrorKind::Color(err) => write!(f, "{err}"),
+            ImportErrorKind::Booking(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn week_plan() -> WeekPlan {
+        WeekPlan::new(Time::new(8, 30).unwrap(), 90, 4).unwrap()
+    }
+
+    #[test]
+    fn export() {
+        let mut plan = week_plan();
+        plan.try_insert_range(
+            Weekday::Tuesday,
+            (Time::new(8, 30).unwrap(), 2),
+            "Lab, \"A\"".into(),
+        )
+        .unwrap()
+        .try_insert(Weekday::Monday, Time::new(13, 0).unwrap(), "B".into())
+        .unwrap()
+        .set_color("B", Color::rgb(0, 0, 128))
+        .set_first_weekday(Weekday::Sunday);
+
+        assert_eq!(
+            Csv::new().render_to_string(&plan),
+            "weekday,start,end,slots,activity,color\r\n\
+             Monday,13:00,14:30,1,B,#000080\r\n\
+             Tuesday,08:30,11:30,2,\"Lab, \"\"A\"\"\",\r\n"
+        );
+
+        let grid = Csv::tsv().grid(true).render_to_string(&plan);
+        let lines: Vec<&str> = grid.lines().collect();
+        assert_eq!(
+            lines[0],
+            "\tSunday\tMonday\tTuesday\tWednesday\tThursday\tFriday\tSaturday"
+        );
+        assert_eq!(lines[1], "08:30\t\t\t\"Lab, \"\"A\"\"\"\t\t\t\t");
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut plan = week_plan();
+        plan.try_insert_range(
+            Weekday::Friday,
+            (Time::new(10, 0).unwrap(), 3),
+            "Multi\nline".into(),
+        )
+        .unwrap()
+        .set_color("Multi\nline", Color::WHITE);
+
+        for csv in [Csv::new(), Csv::tsv()] {
+            let mut imported = week_plan();
+            let count = csv
+                .import(&mut imported, &csv.render_to_string(&plan))
+                .unwrap();
+            assert_eq!(count, 1);
+            assert_eq!(imported, plan);
+        }
+    }
+
+    #[test]
+    fn import() {
+        let input = "Activity;Weekday;Start;End\n\
+                     Math;mon;8:30;11:30\n\
+                     ;;;\n\
+                     Physics;Wednesday;13:00;14:30\n";
+        let mut plan = week_plan();
+        assert_eq!(
+            Csv::new().delimiter(';').import(&mut plan, input).unwrap(),
+            2
+        );
+        assert_eq!(
+            plan.activities(),
+            [("Math".into(), 180), ("Physics".into(), 90)]
+        );
+    }
+
+    #[test]
+    fn import_errors() {
+        let import = |input: &str| {
+            let err = Csv::new().import(&mut week_plan(), input).unwrap_err();
+            (err.row, err.to_string())
+        };
+
+        assert_eq!(import("").0, 1);
+        assert_eq!(
+            import("weekday,start,activity\n"),
+            (1, "row 1: missing column `slots`".to_owned())
+        );
+        assert!(matches!(
+            Csv::new()
+                .import(
+                    &mut week_plan(),
+                    "weekday,start,slots,activity\nMonday,8:30,1,A\nMonday,9:00,1,B\n"
+                )
+                .unwrap_err(),
+            ImportError {
+                row: 3,
+                kind: ImportErrorKind::Booking(Error::InvalidSlot(_))
+            }
+        ));
+        assert!(matches!(
+            Csv::new()
+                .import(
+                    &mut week_plan(),
+                    "weekday,start,slots,activity\nMonday,8:30,1,\"A\nB\"\nMonday,8:30,1,C\n"
+                )
+                .unwrap_err(),
+            ImportError {
+                row: 4,
+                kind: ImportErrorKind::Booking(Error::AlreadyBooked(_))
+            }
+        ));
+        assert_eq!(
+            import("weekday,start,end,slots,activity\nMonday,8:30,9:00,1,A\n").0,
+            2
+        );
+        assert_eq!(
+            import("weekday,start,slots,activity\nMonday,8:30,1,\"A\n").0,
+            2
+        );
+
+        let mut long_slots = WeekPlan::new(Time::new(0, 0).unwrap(), 300, 4).unwrap();
+        assert_eq!(
+            Csv::new()
+                .import(
+                    &mut long_slots,
+                    "weekday,start,slots,activity\nMonday,0:00,255,A\n"
+                )
+                .unwrap_err()
+                .to_string(),
+            "row 2: invalid number of slots \"255\""
+        );
+    }
+}
@@ -0,0 +1,281 @@
+//! Plan file formats. JSON is always available; TOML and YAML need the
+//! `toml` and `yaml` cargo features.
+//!
+//! Whatever the format, documents go through [`migration`] when read, so older
+//! plan versions are upgraded the same way. Reading stops at the first problem;
+//! [`validation::validate`](super::validation::validate) lists every problem
+//! of a JSON plan with its location.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+
+use serde_json::Value;
+
+use super::migration::{self, MigrationError};
+use super::WeekPlan;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Toml,
+    Yaml,
+}
+
+#[derive(Debug)]
+pub enum FormatError {
+    UnknownExtension(String),
+    /// The format was left out of this build.
+    Disabled(Format),
+    Parse(Format, String),
+    Serialize(Format, String),
+    Migration(MigrationError),
+    Io(io::Error),
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::UnknownExtension(path) => write!(
+                f,
+                "don't know the format of `{path}`, expected .json, .toml, .yaml or .yml"
+            ),
+            FormatError::Disabled(format) => write!(
+                f,
+                "{format} plans are not supported by this build, rebuild with `--features {}`",
+                format.feature()
+            ),
+            FormatError::Parse(format, msg) => write!(f, "invalid {format}: {msg}"),
+            FormatError::Serialize(format, msg) => {
+                write!(f, "can't write the plan as {format}: {msg}")
+            }
+            FormatError::Migration(err) => write!(f, "{err}"),
+            FormatError::Io(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+impl From<MigrationError> for FormatError {
+    fn from(err: MigrationError) -> Self {
+        FormatError::Migration(err)
+    }
+}
+
+impl From<io::Error> for FormatError {
+    fn from(err: io::Error) -> Self {
+        FormatError::Io(err)
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Format::Json => "JSON",
+            Format::Toml => "TOML",
+            Format::Yaml => "YAML",
+        })
+    }
+}
+
+impl Format {
+    /// The format matching the extension of `path`, whether or not it is
+    /// enabled.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, FormatError> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+
+        match extension.as_str() {
+            "json" => Ok(Format::Json),
+            "toml" => Ok(Format::Toml),
+            "yaml" | "yml" => Ok(Format::Yaml),
+            _ => Err(FormatError::UnknownExtension(path.display().to_string())),
+        }
+    }
+
+    fn feature(&self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Toml => "toml",
+            Format::Yaml => "yaml",
+        }
+    }
+
+    /// Parses a plan document without interpreting it.
+    pub fn parse(&self, source: &str) -> Result<Value, FormatError> {
+        let parse = |msg: String| FormatError::Parse(*self, msg);
+
+        match self {
+            Format::Json => serde_json::from_str(source).map_err(|err| parse(err.to_string())),
+            #[cfg(feature = "toml")]
+            Format::Toml => toml::from_str(source).map_err(|err| parse(err.to_string())),
+            #[cfg(feature = "yaml")]
+            Format::Yaml => serde_yaml::from_str(source).map_err(|err| parse(err.to_string())),
+            #[allow(unreachable_patterns)]
+            _ => Err(FormatError::Disabled(*self)),
+        }
+    }
+
+    /// Reads a plan, upgrading it to the current version if needed.
+    pub fn parse_plan(&self, source: &str) -> Result<WeekPlan, FormatError> {
+        Ok(migration::from_value(self.parse(source)?)?)
+    }
+
+    /// Writes a plan at the current version.
+    pub fn write_plan(&self, week_plan: &WeekPlan) -> Result<String, FormatError> {
+        let serialize = |msg: String| FormatError::Serialize(*self, msg);
+
+        match self {
+            Format::Json => serde_json::to_string_pretty(week_plan)
+                .map(|json| json + "\n")
+                .map_err(|err| serialize(err.to_string())),
+            #[cfg(feature = "toml")]
+            Format::Toml => toml::to_string(week_plan).map_err(|err| serialize(err.to_string())),
+            #[cfg(feature = "yaml")]
+            Format::Yaml => {
+                serde_yaml::to_string(week_plan).map_err(|err| serialize(err.to_string()))
+            }
+            #[allow(unreachable_patterns)]
+            _ => Err(FormatError::Disabled(*self)),
+        }
+    }
+}
+
+/// Writes a plan to `path` in the format matching its extension. The plan
+/// goes to a temporary file next to it which then replaces it, so that the
+/// file is never left half written and readers see either the old plan or the
+/// new one.
+pub fn save(path: impl AsRef<Path>, week_plan: &WeekPlan) -> Result<(), FormatError> {
+    let path = path.as_ref();
+    let contents = Format::from_path(path)?.write_plan(week_plan)?;
+
+    let name = path.file_name().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("`{}` is not a file", path.display()),
+        )
+    })?;
+    let temporary = path.with_file_name(format!(".{}.tmp", name.to_string_lossy()));
+
+    let written = File::create(&temporary).and_then(|mut file| {
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()
+    });
+    if let Err(err) = written.and_then(|()| std::fs::rename(&temporary, path)) {
+        let _ = std::fs::remove_file(&temporary);
+        return Err(err.into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::weekplan::{Color, Time, Weekday};
+
+    fn week_plan() -> WeekPlan {
+        let mut week_plan = WeekPlan::new(Time::new(8, 30).unwrap(), 90, 4).unwrap();
+        week_plan
+            .try_insert_range(Weekday::Monday, (Time::new(8, 30).unwrap(), 2), "A".into())
+            .unwrap()
+            .set_color("A", Color::WHITE)
+            .set_first_weekday(Weekday::Sunday);
+        week_plan
+    }
+
+    #[test]
+    fn from_path() {
+        assert_eq!(Format::from_path("plan.json").unwrap(), Format::Json);
+        assert_eq!(Format::from_path("a/plan.TOML").unwrap(), Format::Toml);
+        assert_eq!(Format::from_path("plan.yml").unwrap(), Format::Yaml);
+        assert!(matches!(
+            Format::from_path("plan"),
+            Err(FormatError::UnknownExtension(_))
+        ));
+    }
+
+    #[test]
+    fn save() {
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("weekly_planner-{}-save.json", std::process::id()));
+
+        super::save(&path, &week_plan()).unwrap();
+        let read = Format::Json
+            .parse_plan(&std::fs::read_to_string(&path).unwrap())
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(read, week_plan());
+
+        let path = dir.join(format!("weekly_planner-{}-save.txt", std::process::id()));
+        assert!(matches!(
+            super::save(&path, &week_plan()),
+            Err(FormatError::UnknownExtension(_))
+        ));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn round_trip() {
+        let formats = [
+            (Format::Json, true),
+            (Format::Toml, cfg!(feature = "toml")),
+            (Format::Yaml, cfg!(feature = "yaml")),
+        ];
+
+        for (format, enabled) in formats {
+            let written = format.write_plan(&week_plan());
+            if !enabled {
+                assert!(matches!(written, Err(FormatError::Disabled(_))));
+                continue;
+            }
+
+            let read = format.parse_plan(&written.unwrap()).unwrap();
+            assert_eq!(read, week_plan());
+        }
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn toml() {
+        let source = r##"
+            # Lessons start after the first bus
+            version = 2
+            start = "08:30"
+            slot_duration = 90
+            slots = 4
+
+            [plan]
+            "Monday 08:30" = "A"
+            "Monday 10:00" = "A"
+
+            [colors]
+            A = "#fff"
+        "##;
+        let mut expected = week_plan();
+        expected.set_first_weekday(Weekday::Monday);
+        assert_eq!(Format::Toml.parse_plan(source).unwrap(), expected);
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn yaml() {
+        // Version 1 had the start as an object
+        let source = "
+            plan:
+              Monday 08:30: A
+              Monday 10:00: A
+            start: {hour: 8, minute: 30}
+            slot_duration: 90
+            slots: 4
+            first_weekday: Sunday
+            colors: {A: '#ffffff'}
+        ";
+        assert_eq!(Format::Yaml.parse_plan(source).unwrap(), week_plan());
+    }
+}
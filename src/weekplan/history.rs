@@ -0,0 +1,657 @@
+//! Undoable edits of a plan.
+//!
+//! Every change is an [`Edit`] that knows its [`inverse`](Edit::inverse), and
+//! [`History`] applies them to a plan while keeping what it takes to undo and
+//! redo them. It also logs what happened, as [`Entry`]s that can be saved as
+//! JSON Lines next to the plan file:
+//!
+//! ```text
+//! {"at":1760774400,"action":"apply","edit":{"op":"book","weekday":"Monday","time":"08:30","activity":"Math"}}
+//! {"at":1760774402,"action":"undo","edit":{"op":"book","weekday":"Monday","time":"08:30","activity":"Math"}}
+//! ```
+//!
+//! Replaying a log with [`replay`] on the plan the history started from gives
+//! the plan the history ended with.
+
+use std::fmt;
+use std::io::{self, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use super::{Activity, Color, Error, Slot, Time, WeekPlan, Weekday};
+
+/// A change of a plan, holding what the plan had before so that it can be
+/// undone.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Edit {
+    /// Books a free slot.
+    Book {
+        weekday: Weekday,
+        time: Time,
+        activity: Activity,
+    },
+    /// Frees a slot booked with `activity`.
+    Free {
+        weekday: Weekday,
+        time: Time,
+        activity: Activity,
+    },
+    /// Changes the activity booked in a slot.
+    Rename {
+        weekday: Weekday,
+        time: Time,
+        from: Activity,
+        to: Activity,
+    },
+    /// Sets or resets the color of an activity.
+    SetColor {
+        activity: Activity,
+        from: Option<Color>,
+        to: Option<Color>,
+    },
+    SetFirstWeekday {
+        from: Weekday,
+        to: Weekday,
+    },
+    /// Edits applied and undone as one.
+    Group {
+        edits: Vec<Edit>,
+    },
+}
+
+/// Why an [`Edit`] couldn't be applied. The plan is left as it was.
+#[derive(Debug)]
+pub enum EditError {
+    /// The plan refused the edit.
+    Plan(Error),
+    /// The plan doesn't hold what the edit expects to replace, for instance
+    /// when replaying a log on another plan.
+    Conflict(Edit),
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EditError::Plan(err) => write!(f, "{err}"),
+            EditError::Conflict(edit) => {
+                write!(f, "can't {edit}: the plan doesn't match")
+            }
+        }
+    }
+}
+
+impl std::error::Error for EditError {}
+
+impl From<Error> for EditError {
+    fn from(err: Error) -> Self {
+        EditError::Plan(err)
+    }
+}
+
+impl fmt::Display for Edit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Edit::Book {
+                weekday,
+                time,
+                activity,
+            } => write!(f, "book {activity} on {weekday} {time}"),
+            Edit::Free {
+                weekday,
+                time,
+                activity,
+            } => write!(f, "free {activity} on {weekday} {time}"),
+            Edit::Rename {
+                weekday,
+                time,
+                from,
+                to,
+            } => write!(f, "rename {from} to {to} on {weekday} {time}"),
+            Edit::SetColor {
+                activity,
+                to: Some(color),
+                ..
+            } => write!(f, "color {activity} {color}"),
+            Edit::SetColor {
+                activity, to: None, ..
+            } => write!(f, "reset the color of {activity}"),
+            Edit::SetFirstWeekday { to, .. } => write!(f, "start weeks on {to}"),
+            Edit::Group { edits } => match edits.as_slice() {
+                [edit] => write!(f, "{edit}"),
+                edits => write!(f, "{} edits", edits.len()),
+            },
+        }
+    }
+}
+
+impl Edit {
+    /// The edit undoing this one.
+    pub fn inverse(&self) -> Edit {
+        match self.clone() {
+            Edit::Book {
+                weekday,
+                time,
+                activity,
+            } => Edit::Free {
+                weekday,
+                time,
+                activity,
+            },
+            Edit::Free {
+                weekday,
+                time,
+                activity,
+            } => Edit::Book {
+                weekday,
+                time,
+                activity,
+            },
+            Edit::Rename {
+                weekday,
+                time,
+                from,
+                to,
+            } => Edit::Rename {
+                weekday,
+                time,
+                from: to,
+                to: from,
+            },
+            Edit::SetColor { activity, from, to } => Edit::SetColor {
+                activity,
+                from: to,
+                to: from,
+            },
+            Edit::SetFirstWeekday { from, to } => Edit::SetFirstWeekday { from: to, to: from },
+            Edit::Group { edits } => Edit::Group {
+                edits: edits.iter().rev().map(Edit::inverse).collect(),
+            },
+        }
+    }
+
+    /// Applies the edit, or leaves `week_plan` untouched on error.
+    pub fn apply(&self, week_plan: &mut WeekPlan) -> Result<(), EditError> {
+        let conflict = || EditError::Conflict(self.clone());
+
+        match self {
+            Edit::Book {
+                weekday,
+                time,
+                activity,
+            } => {
+                week_plan.try_insert(*weekday, *time, activity.clone())?;
+            }
+            Edit::Free {
+                weekday,
+                time,
+                activity,
+            } => {
+                if week_plan.get(*weekday, *time) != Some(activity) {
+                    return Err(conflict());
+                }
+                week_plan.remove(*weekday, *time);
+            }
+            Edit::Rename {
+                weekday,
+                time,
+                from,
+                to,
+            } => match week_plan.plan.get_mut(&Slot(*weekday, *time)) {
+                Some(activity) if activity == from => *activity = to.clone(),
+                _ => return Err(conflict()),
+            },
+            Edit::SetColor { activity, from, to } => {
+                if week_plan.colors.get(activity) != from.as_ref() {
+                    return Err(conflict());
+                }
+                match to {
+                    Some(color) => week_plan.colors.insert(activity.clone(), *color),
+                    None => week_plan.colors.remove(activity),
+                };
+            }
+            Edit::SetFirstWeekday { from, to } => {
+                if week_plan.first_weekday != *from {
+                    return Err(conflict());
+                }
+                week_plan.first_weekday = *to;
+            }
+            Edit::Group { edits } => {
+                for (i, edit) in edits.iter().enumerate() {
+                    if let Err(err) = edit.apply(week_plan) {
+                        // Undoing what was just applied can't fail
+                        for edit in edits[..i].iter().rev() {
+                            let _ = edit.inverse().apply(week_plan);
+                        }
+                        return Err(err);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Apply,
+    Undo,
+    Redo,
+}
+
+/// Something that happened to a plan in a [`History`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    /// Seconds since the Unix epoch.
+    pub at: u64,
+    pub action: Action,
+    /// The edit applied, undone or redone.
+    pub edit: Edit,
+}
+
+impl Entry {
+    /// The edit the entry made to the plan: the inverse of the logged one
+    /// for undos.
+    pub fn applied(&self) -> Edit {
+        match self.action {
+            Action::Apply | Action::Redo => self.edit.clone(),
+            Action::Undo => self.edit.inverse(),
+        }
+    }
+}
+
+/// Applies the edits of a log in order, stopping at the first that fails.
+pub fn replay(week_plan: &mut WeekPlan, entries: &[Entry]) -> Result<(), EditError> {
+    entries
+        .iter()
+        .try_for_each(|entry| entry.applied().apply(week_plan))
+}
+
+/// Writes entries as JSON Lines, one entry per line.
+pub fn write_log(entries: &[Entry], out: &mut dyn Write) -> io::Result<()> {
+    for entry in entries {
+        serde_json::to_writer(&mut *out, entry)?;
+        out.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+/// Reads entries written by [`write_log`], skipping blank lines.
+pub fn read_log(input: &str) -> serde_json::Result<Vec<Entry>> {
+    input
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(serde_json::from_str)
+        .collect()
+}
+
+/// A plan with the edits made to it, which can be undone and redone.
+#[derive(Debug)]
+pub struct History {
+    week_plan: WeekPlan,
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+    log: Vec<Entry>,
+}
+
+impl History {
+    pub fn new(week_plan: WeekPlan) -> Self {
+        History {
+            week_plan,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            log: Vec::new(),
+        }
+    }
+
+    pub fn plan(&self) -> &WeekPlan {
+        &self.week_plan
+    }
+
+    pub fn into_plan(self) -> WeekPlan {
+        self.week_plan
+    }
+
+    /// Applies an edit, which can then be undone. Redoing the edits undone
+    /// before is no longer possible.
+    pub fn apply(&mut self, edit: Edit) -> Result<&mut Self, EditError> {
+        edit.apply(&mut self.week_plan)?;
+        self.record(Action::Apply, &edit);
+        self.undo.push(edit);
+        self.redo.clear();
+        Ok(self)
+    }
+
+    /// Undoes the last edit, returning it, or `None` if there is nothing to
+    /// undo.
+    pub fn undo(&mut self) -> Option<&Edit> {
+        let edit = self.undo.pop()?;
+        // The plan is as the edit left it, so undoing it can't fail
+        let _ = edit.inverse().apply(&mut self.week_plan);
+        self.record(Action::Undo, &edit);
+        self.redo.push(edit);
+        self.redo.last()
+    }
+
+    /// Applies the last undone edit again, returning it, or `None` if there
+    /// is nothing to redo.
+    pub fn redo(&mut self) -> Option<&Edit> {
+        let edit = self.redo.pop()?;
+        let _ = edit.apply(&mut self.week_plan);
+        self.record(Action::Redo, &edit);
+        self.undo.push(edit);
+        self.undo.last()
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// What happened since the history was created or the log last taken.
+    pub fn log(&self) -> &[Entry] {
+        &self.log
+    }
+
+    /// Empties the log, returning its entries, for instance to append them
+    /// to a log file when saving the plan.
+    pub fn take_log(&mut self) -> Vec<Entry> {
+        std::mem::take(&mut self.log)
+    }
+
+    fn record(&mut self, action: Action, edit: &Edit) {
+        let at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |now| now.as_secs());
+        self.log.push(Entry {
+            at,
+            action,
+            edit: edit.clone(),
+        });
+    }
+
+    /// Books `activity` in a slot, renames the activity booked there, or
+    /// frees it if `activity` is `None`. Does nothing if the slot already
+    /// holds `activity`.
+    pub fn set(
+        &mut self,
+        weekday: Weekday,
+        time: Time,
+        activity: Option<Activity>,
+    ) -> Result<&mut Self, EditError> {
+        let edit = match (self.week_plan.get(weekday, time).cloned(), activity) {
+            (None, None) => return Ok(self),
+            (Some(from), Some(to)) if from == to => return Ok(self),
+            (None, Some(activity)) => Edit::Book {
+                weekday,
+                time,
+                activity,
+            },
+            (Some(activity), None) => Edit::Free {
+                weekday,
+                time,
+                activity,
+            },
+            (Some(from), Some(to)) => Edit::Rename {
+                weekday,
+                time,
+                from,
+                to,
+            },
+        };
+        self.apply(edit)
+    }
+
+    /// Books consecutive slots as one edit, like
+    /// [`WeekPlan::try_insert_range`].
+    pub fn book_range(
+        &mut self,
+        weekday: Weekday,
+        slots: (Time, u8),
+        activity: Activity,
+    ) -> Result<&mut Self, EditError> {
+        let (start, len) = slots;
+        let edits = (0..len)
+            .map(|i| {
+                let time = start
+                    .try_sum(u16::from(i) * self.week_plan.slot_duration)
+                    .ok_or(Error::OutOfBounds)?;
+                Ok(Edit::Book {
+                    weekday,
+                    time,
+                    activity: activity.clone(),
+                })
+            })
+            .collect::<Result<_, Error>>()?;
+        self.apply(Edit::Group { edits })
+    }
+
+    /// Moves the booking of a slot to a free one, as one edit.
+    pub fn move_booking(
+        &mut self,
+        from: (Weekday, Time),
+        to: (Weekday, Time),
+    ) -> Result<&mut Self, EditError> {
+        let (weekday, time) = from;
+        let activity = self.week_plan.get(weekday, time).cloned().ok_or_else(|| {
+            EditError::Conflict(Edit::Free {
+                weekday,
+                time,
+                activity: Activity::new(),
+            })
+        })?;
+
+        self.apply(Edit::Group {
+            edits: vec![
+                Edit::Free {
+                    weekday,
+                    time,
+                    activity: activity.clone(),
+                },
+                Edit::Book {
+                    weekday: to.0,
+                    time: to.1,
+                    activity,
+                },
+            ],
+        })
+    }
+
+    /// Sets the color of an activity, or resets it to the one derived from
+    /// its name.
+    pub fn set_color(
+        &mut self,
+        activity: impl Into<Activity>,
+        color: Option<Color>,
+    ) -> Result<&mut Self, EditError> {
+        let activity = activity.into();
+        let from = self.week_plan.colors.get(&activity).copied();
+        if from == color {
+            return Ok(self);
+        }
+        self.apply(Edit::SetColor {
+            activity,
+            from,
+            to: color,
+        })
+    }
+
+    pub fn set_first_weekday(&mut self, weekday: Weekday) -> Result<&mut Self, EditError> {
+        let from = self.week_plan.first_weekday;
+        if from == weekday {
+            return Ok(self);
+        }
+        self.apply(Edit::SetFirstWeekday { from, to: weekday })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn time(hour: u8, minute: u8) -> Time {
+        Time::new(hour, minute).unwrap()
+    }
+
+    fn week_plan() -> WeekPlan {
+        let mut week_plan = WeekPlan::new(time(8, 30), 90, 3).unwrap();
+        week_plan
+            .try_insert(Weekday::Monday, time(8, 30), "A".into())
+            .unwrap();
+        week_plan
+    }
+
+    #[test]
+    fn inverses() {
+        let edits = [
+            Edit::Book {
+                weekday: Weekday::Friday,
+                time: time(10, 0),
+                activity: "B".into(),
+            },
+            Edit::Rename {
+                weekday: Weekday::Monday,
+                time: time(8, 30),
+                from: "A".into(),
+                to: "C".into(),
+            },
+            Edit::SetColor {
+                activity: "C".into(),
+                from: None,
+                to: Some(Color::WHITE),
+            },
+            Edit::SetFirstWeekday {
+                from: Weekday::Monday,
+                to: Weekday::Sunday,
+            },
+        ];
+        let group = Edit::Group {
+            edits: edits.to_vec(),
+        };
+
+        for edit in edits.iter().chain([&group]) {
+            let mut edited = week_plan();
+            edit.apply(&mut edited).unwrap();
+            assert_ne!(edited, week_plan(), "{edit}");
+            edit.inverse().apply(&mut edited).unwrap();
+            assert_eq!(edited, week_plan(), "{edit}");
+            assert_eq!(&edit.inverse().inverse(), edit);
+        }
+    }
+
+    #[test]
+    fn failed_edits_change_nothing() {
+        let mut history = History::new(week_plan());
+
+        // The second slot is past the last one, so the first one is not
+        // booked either
+        let err = history
+            .book_range(Weekday::Sunday, (time(11, 30), 2), "B".into())
+            .unwrap_err();
+        assert!(matches!(err, EditError::Plan(Error::InvalidSlot(_))));
+
+        let err = history
+            .apply(Edit::Free {
+                weekday: Weekday::Monday,
+                time: time(8, 30),
+                activity: "B".into(),
+            })
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "can't free B on Monday 08:30: the plan doesn't match"
+        );
+
+        let err = history
+            .move_booking(
+                (Weekday::Monday, time(8, 30)),
+                (Weekday::Monday, time(7, 0)),
+            )
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Invalid slot 07:00");
+
+        assert_eq!(history.plan(), &week_plan());
+        assert!(!history.can_undo());
+        assert!(history.log().is_empty());
+    }
+
+    #[test]
+    fn undo_redo() {
+        let mut history = History::new(week_plan());
+        history
+            .book_range(Weekday::Tuesday, (time(8, 30), 2), "B".into())
+            .unwrap()
+            .move_booking(
+                (Weekday::Monday, time(8, 30)),
+                (Weekday::Friday, time(11, 30)),
+            )
+            .unwrap()
+            .set(Weekday::Friday, time(11, 30), Some("C".into()))
+            .unwrap();
+        let edited = history.plan().bookings().len();
+        assert_eq!(edited, 3);
+
+        assert_eq!(
+            history.undo().unwrap().to_string(),
+            "rename A to C on Friday 11:30"
+        );
+        assert_eq!(history.undo().unwrap().to_string(), "2 edits");
+        assert_eq!(
+            history.plan().get(Weekday::Monday, time(8, 30)).unwrap(),
+            "A"
+        );
+        assert!(history.redo().is_some());
+        assert_eq!(
+            history.plan().get(Weekday::Friday, time(11, 30)).unwrap(),
+            "A"
+        );
+
+        // A new edit drops what was undone
+        history.set(Weekday::Friday, time(11, 30), None).unwrap();
+        assert!(!history.can_redo());
+        assert!(history.redo().is_none());
+
+        while history.undo().is_some() {}
+        assert_eq!(history.plan(), &week_plan());
+    }
+
+    #[test]
+    fn log() {
+        let mut history = History::new(week_plan());
+        history
+            .set_color("A", Some(Color::rgb(0, 0, 128)))
+            .unwrap()
+            .set_first_weekday(Weekday::Sunday)
+            .unwrap()
+            .set(Weekday::Monday, time(8, 30), None)
+            .unwrap();
+        history.undo();
+        history.undo();
+        history.redo();
+
+        let mut out = Vec::new();
+        write_log(history.log(), &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(out.lines().count(), 6);
+        assert!(out.lines().nth(4).unwrap().contains(
+            r#""action":"undo","edit":{"op":"set_first_weekday","from":"Monday","to":"Sunday"}}"#
+        ));
+
+        let entries = read_log(&format!("\n{out}\n")).unwrap();
+        assert_eq!(entries, history.take_log());
+        assert!(history.log().is_empty());
+
+        let mut replayed = week_plan();
+        replay(&mut replayed, &entries).unwrap();
+        assert_eq!(&replayed, history.plan());
+
+        // The log only applies to the plan it started from
+        let err = replay(&mut replayed, &entries).unwrap_err();
+        assert!(matches!(err, EditError::Conflict(Edit::SetColor { .. })));
+    }
+}
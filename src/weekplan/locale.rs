@@ -0,0 +1,186 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use super::time::FormattedTime;
+use super::{Time, Weekday};
+
+/// A language the crate ships translations for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Language {
+    #[default]
+    English,
+    Italian,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::Italian];
+
+    /// The ISO 639-1 code of the language.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::Italian => "it",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseLanguageError(String);
+
+impl fmt::Display for ParseLanguageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let codes: Vec<_> = Language::ALL.iter().map(Language::code).collect();
+        write!(
+            f,
+            "unknown language {:?}, expected one of {}",
+            self.0,
+            codes.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for ParseLanguageError {}
+
+/// Accepts ISO 639-1 codes (`it`) and English names (`Italian`).
+impl FromStr for Language {
+    type Err = ParseLanguageError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "en" | "english" => Ok(Language::English),
+            "it" | "italian" => Ok(Language::Italian),
+            _ => Err(ParseLanguageError(s.to_owned())),
+        }
+    }
+}
+
+/// Every piece of text the renderers print.
+///
+/// Built-in translations are available through [`Locale::builtin`]. Others
+/// can be loaded from JSON translation files, where missing fields fall back
+/// to English:
+///
+/// ```json
+/// {
+///   "code": "fr",
+///   "title": "Emploi du temps",
+///   "weekdays": ["Lundi", "Mardi", "Mercredi", "Jeudi", "Vendredi", "Samedi", "Dimanche"]
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Locale {
+    /// The language tag, used as the HTML `lang` attribute.
+    pub code: String,
+    pub title: String,
+    pub legend: String,
+    pub activity: String,
+    pub hours: String,
+    /// The label of the download button of the HTML editor.
+    pub download: String,
+    /// Day names from Monday to Sunday.
+    pub weekdays: [String; 7],
+    pub short_weekdays: [String; 7],
+    /// A [`Time::format`] pattern.
+    pub time_format: String,
+}
+
+impl Locale {
+    pub fn builtin(language: Language) -> Self {
+        let (title, legend, activity, hours, download) = match language {
+            Language::English => (
+                "Weekly plan",
+                "Legend",
+                "Activity",
+                "Hours",
+                "Download plan",
+            ),
+            Language::Italian => (
+                "Orario settimanale",
+                "Legenda",
+                "Attività",
+                "Ore",
+                "Scarica il piano",
+            ),
+        };
+
+        Locale {
+            code: language.code().to_owned(),
+            title: title.to_owned(),
+            legend: legend.to_owned(),
+            activity: activity.to_owned(),
+            hours: hours.to_owned(),
+            download: download.to_owned(),
+            weekdays: Weekday::ALL.map(|weekday| weekday.name(language).to_owned()),
+            short_weekdays: Weekday::ALL.map(|weekday| weekday.short_name(language).to_owned()),
+            time_format: Time::PATTERN_24H.to_owned(),
+        }
+    }
+
+    /// Reads a JSON translation file.
+    pub fn from_reader<R: std::io::Read>(reader: R) -> serde_json::Result<Self> {
+        serde_json::from_reader(reader)
+    }
+
+    pub fn weekday(&self, weekday: Weekday) -> &str {
+        &self.weekdays[weekday as usize]
+    }
+
+    pub fn short_weekday(&self, weekday: Weekday) -> &str {
+        &self.short_weekdays[weekday as usize]
+    }
+
+    pub fn time(&self, time: Time) -> FormattedTime<'_> {
+        time.format(&self.time_format)
+    }
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Locale::builtin(Language::English)
+    }
+}
+
+impl From<Language> for Locale {
+    fn from(language: Language) -> Self {
+        Locale::builtin(language)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn builtin() {
+        let italian = Locale::builtin(Language::Italian);
+
+        assert_eq!(italian.code, "it");
+        assert_eq!(italian.weekday(Weekday::Friday), "Venerdì");
+        assert_eq!(italian.short_weekday(Weekday::Friday), "Ven");
+        assert_eq!(italian.time(Time::new(8, 30).unwrap()).to_string(), "08:30");
+    }
+
+    #[test]
+    fn translation_file() {
+        let json = r#"{
+            "code": "fr",
+            "title": "Emploi du temps",
+            "weekdays": ["Lundi", "Mardi", "Mercredi", "Jeudi", "Vendredi", "Samedi", "Dimanche"],
+            "time_format": "H'h'mm"
+        }"#;
+        let french = Locale::from_reader(json.as_bytes()).unwrap();
+
+        assert_eq!(french.weekday(Weekday::Sunday), "Dimanche");
+        assert_eq!(french.time(Time::new(8, 30).unwrap()).to_string(), "8h30");
+        assert_eq!(french.legend, "Legend");
+    }
+
+    #[test]
+    fn parse_language() {
+        assert_eq!("it".parse(), Ok(Language::Italian));
+        assert_eq!("English".parse(), Ok(Language::English));
+        assert!("fr".parse::<Language>().is_err());
+    }
+}
@@ -0,0 +1,164 @@
+use std::fmt;
+
+use serde_json::{Map, Value};
+
+use super::WeekPlan;
+
+/// The plan file version written by this crate.
+pub const CURRENT_VERSION: u32 = 2;
+
+/// Documents without a `version` field predate versioning.
+const UNVERSIONED: u32 = 1;
+
+type Migration = fn(&mut Map<String, Value>) -> Result<(), MigrationError>;
+
+/// Each step upgrades a document from version `n` to `n + 1`, where `n` is
+/// its index in the list plus one.
+const MIGRATIONS: &[Migration] = &[v1_to_v2];
+
+#[derive(Debug)]
+pub enum MigrationError {
+    NotAnObject,
+    InvalidVersion(Value),
+    UnsupportedVersion(u32),
+    Malformed(String),
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MigrationError::NotAnObject => write!(f, "plan document is not an object"),
+            MigrationError::InvalidVersion(value) => write!(f, "invalid version {value}"),
+            MigrationError::UnsupportedVersion(version) => write!(
+                f,
+                "version {version} is newer than the supported version {CURRENT_VERSION}"
+            ),
+            MigrationError::Malformed(msg) => write!(f, "malformed plan document: {msg}"),
+            MigrationError::Json(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {}
+
+impl From<serde_json::Error> for MigrationError {
+    fn from(err: serde_json::Error) -> Self {
+        MigrationError::Json(err)
+    }
+}
+
+/// Returns the version of a plan document.
+pub fn version_of(document: &Value) -> Result<u32, MigrationError> {
+    let document = document.as_object().ok_or(MigrationError::NotAnObject)?;
+
+    match document.get("version") {
+        None => Ok(UNVERSIONED),
+        Some(value) => value
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .filter(|&version| version >= UNVERSIONED)
+            .ok_or_else(|| MigrationError::InvalidVersion(value.clone())),
+    }
+}
+
+/// Upgrades a plan document to [`CURRENT_VERSION`], applying every migration
+/// step between its version and the current one.
+pub fn migrate(mut document: Value) -> Result<Value, MigrationError> {
+    let version = version_of(&document)?;
+    if version > CURRENT_VERSION {
+        return Err(MigrationError::UnsupportedVersion(version));
+    }
+
+    let map = document
+        .as_object_mut()
+        .ok_or(MigrationError::NotAnObject)?;
+    for (step, migration) in MIGRATIONS.iter().enumerate() {
+        let from = UNVERSIONED + step as u32;
+        if from >= version {
+            migration(map)?;
+            map.insert("version".to_owned(), Value::from(from + 1));
+        }
+    }
+
+    Ok(document)
+}
+
+/// Builds a [`WeekPlan`] out of a document of any supported version.
+pub fn from_value(document: Value) -> Result<WeekPlan, MigrationError> {
+    Ok(serde_json::from_value(migrate(document)?)?)
+}
+
+/// Reads a JSON plan document of any supported version.
+pub fn from_reader<R: std::io::Read>(reader: R) -> Result<WeekPlan, MigrationError> {
+    from_value(serde_json::from_reader(reader)?)
+}
+
+/// Version 2 added the `version` field and switched `start` from a
+/// `{"hour", "minute"}` object to an `"HH:MM"` string.
+fn v1_to_v2(document: &mut Map<String, Value>) -> Result<(), MigrationError> {
+    if let Some(start) = document.get_mut("start") {
+        if let Value::Object(time) = start {
+            let field = |name: &str| {
+                time.get(name)
+                    .and_then(Value::as_u64)
+                    .ok_or_else(|| MigrationError::Malformed(format!("missing start.{name}")))
+            };
+            let (hour, minute) = (field("hour")?, field("minute")?);
+            *start = Value::from(format!("{hour:02}:{minute:02}"));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::weekplan::{Time, Weekday};
+
+    #[test]
+    fn migrate_v1() {
+        let v1 = serde_json::json!({
+            "plan": { "Monday 08:30": "AAA" },
+            "start": { "hour": 8, "minute": 30 },
+            "slot_duration": 90,
+            "slots": 7
+        });
+        assert_eq!(version_of(&v1).unwrap(), 1);
+
+        let migrated = migrate(v1).unwrap();
+        assert_eq!(migrated["version"], CURRENT_VERSION);
+        assert_eq!(migrated["start"], "08:30");
+
+        let mut expected = WeekPlan::new(Time::new(8, 30).unwrap(), 90, 7).unwrap();
+        expected
+            .try_insert(Weekday::Monday, Time::new(8, 30).unwrap(), "AAA".into())
+            .unwrap();
+        assert_eq!(from_value(migrated).unwrap(), expected);
+    }
+
+    #[test]
+    fn migrate_current_is_noop() {
+        let plan = WeekPlan::new(Time::new(8, 30).unwrap(), 90, 7).unwrap();
+        let document = serde_json::to_value(&plan).unwrap();
+
+        assert_eq!(document["version"], CURRENT_VERSION);
+        assert_eq!(migrate(document.clone()).unwrap(), document);
+    }
+
+    #[test]
+    fn reject_unknown_versions() {
+        let future = serde_json::json!({ "version": CURRENT_VERSION + 1 });
+        assert!(matches!(
+            migrate(future),
+            Err(MigrationError::UnsupportedVersion(_))
+        ));
+
+        let invalid = serde_json::json!({ "version": "two" });
+        assert!(matches!(
+            migrate(invalid),
+            Err(MigrationError::InvalidVersion(_))
+        ));
+    }
+}
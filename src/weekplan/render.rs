@@ -0,0 +1,138 @@
+use std::io::{self, Write};
+
+use super::{Activity, Time, WeekPlan};
+
+pub use html::{Html, ParseThemeError, Theme, DEFAULT_TEMPLATE};
+pub use ical::ICalendar;
+pub use markup::{AsciiDoc, Layout, Markdown};
+pub use pdf::{PageSize, ParsePageSizeError, Pdf};
+pub use svg::Svg;
+pub use terminal::Terminal;
+
+mod html;
+mod ical;
+mod markup;
+mod pdf;
+mod svg;
+mod terminal;
+mod text;
+
+/// An output format for a [`WeekPlan`].
+pub trait Render {
+    fn render(&self, week_plan: &WeekPlan, out: &mut dyn Write) -> io::Result<()>;
+
+    fn render_to_vec(&self, week_plan: &WeekPlan) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.render(week_plan, &mut out)
+            .expect("writing to a Vec never fails");
+        out
+    }
+}
+
+/// A cell of the timetable, covering `span` consecutive slots of a day.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Cell<'a> {
+    pub day: usize,
+    pub slot: usize,
+    pub span: usize,
+    pub activity: &'a Activity,
+}
+
+impl Cell<'_> {
+    /// When the cell starts and ends, given the start of each slot as in
+    /// `WeekPlan::to_table`. Cells running past midnight end at
+    /// [`Time::END_OF_DAY`].
+    pub fn interval(&self, times: &[Time], slot_duration: u16) -> (Time, Time) {
+        let from = times[self.slot];
+        let minutes = u16::try_from(self.span)
+            .unwrap_or(u16::MAX)
+            .saturating_mul(slot_duration);
+        (from, from.try_sum(minutes).unwrap_or(Time::END_OF_DAY))
+    }
+}
+
+/// Splits a table laid out as in `WeekPlan::to_table` into cells, merging
+/// consecutive slots of a day booked for the same activity. Empty slots are
+/// never merged.
+pub(crate) fn merge_cells(table: &[Activity], slots: usize) -> Vec<Cell<'_>> {
+    let mut cells: Vec<Cell> = Vec::new();
+
+    for (day, column) in table.chunks(slots.max(1)).enumerate() {
+        for (slot, activity) in column.iter().enumerate() {
+            match cells.last_mut() {
+                Some(last)
+                    if last.day == day && !activity.is_empty() && last.activity == activity =>
+                {
+                    last.span += 1;
+                }
+                _ => cells.push(Cell {
+                    day,
+                    slot,
+                    span: 1,
+                    activity,
+                }),
+            }
+        }
+    }
+
+    cells
+}
+
+/// Formats a coordinate with at most two decimals and no trailing zeros.
+pub(crate) fn number(value: f64) -> String {
+    let formatted = format!("{value:.2}");
+    formatted
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_owned()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn merge() {
+        let table: Vec<Activity> = ["A", "A", "", "", "B", "A", "A", "A"]
+            .map(String::from)
+            .into();
+        let cells = merge_cells(&table, 4);
+        let spans: Vec<_> = cells
+            .iter()
+            .map(|cell| (cell.day, cell.slot, cell.span, cell.activity.as_str()))
+            .collect();
+
+        assert_eq!(
+            spans,
+            [
+                (0, 0, 2, "A"),
+                (0, 2, 1, ""),
+                (0, 3, 1, ""),
+                (1, 0, 1, "B"),
+                (1, 1, 3, "A"),
+            ]
+        );
+    }
+
+    #[test]
+    fn interval() {
+        let table: Vec<Activity> = ["A", "A", "B"].map(String::from).into();
+        let times =
+            [(21, 0), (22, 30), (23, 0)].map(|(hour, minute)| Time::new(hour, minute).unwrap());
+        let cells = merge_cells(&table, 3);
+
+        assert_eq!(cells[0].interval(&times, 90), (times[0], Time::END_OF_DAY));
+        assert_eq!(
+            cells[1].interval(&times, 30),
+            (times[2], Time::new(23, 30).unwrap())
+        );
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(number(4.0), "4");
+        assert_eq!(number(1.5), "1.5");
+        assert_eq!(number(0.75), "0.75");
+        assert_eq!(number(2.0 / 3.0), "0.67");
+    }
+}
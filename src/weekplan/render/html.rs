@@ -0,0 +1,469 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use super::{number, Render};
+use crate::weekplan::locale::Locale;
+use crate::weekplan::WeekPlan;
+
+/// A built-in stylesheet for [`Html`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Theme {
+    #[default]
+    Light,
+    Dark,
+    HighContrast,
+    Print,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 4] = [Theme::Light, Theme::Dark, Theme::HighContrast, Theme::Print];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Theme::Light => "light",
+            Theme::Dark => "dark",
+            Theme::HighContrast => "high-contrast",
+            Theme::Print => "print",
+        }
+    }
+
+    pub fn css(&self) -> &'static str {
+        match self {
+            Theme::Light => include_str!("html/light.css"),
+            Theme::Dark => include_str!("html/dark.css"),
+            Theme::HighContrast => include_str!("html/high-contrast.css"),
+            Theme::Print => include_str!("html/print.css"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseThemeError(String);
+
+impl fmt::Display for ParseThemeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<_> = Theme::ALL.iter().map(Theme::name).collect();
+        write!(
+            f,
+            "unknown theme {:?}, expected one of {}",
+            self.0,
+            names.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for ParseThemeError {}
+
+impl FromStr for Theme {
+    type Err = ParseThemeError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Theme::ALL
+            .into_iter()
+            .find(|theme| theme.name().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| ParseThemeError(s.to_owned()))
+    }
+}
+
+/// The page [`Html`] fills in by default.
+pub const DEFAULT_TEMPLATE: &str = include_str!("html/template.html");
+
+const EDITOR_SCRIPT: &str = include_str!("html/editor.js");
+
+/// Renders a plan as a standalone HTML page.
+///
+/// The page is built from a template where these placeholders are replaced:
+///
+/// - `{{lang}}`: the language tag of the locale
+/// - `{{title}}`: the localized page title
+/// - `{{css}}`: the stylesheet
+/// - `{{table}}`: the timetable
+/// - `{{legend}}`: the activities with their colors and weekly hours, or
+///   nothing if the legend is disabled
+/// - `{{editor}}`: the download button and scripts of the editor, or nothing
+///   if the editor is disabled
+///
+/// Unknown placeholders are left untouched.
+#[derive(Debug, Clone)]
+pub struct Html {
+    locale: Locale,
+    css: Cow<'static, str>,
+    template: Cow<'static, str>,
+    colors: bool,
+    legend: bool,
+    editor: bool,
+}
+
+impl Default for Html {
+    fn default() -> Self {
+        Html {
+            locale: Locale::default(),
+            css: Theme::default().css().into(),
+            template: DEFAULT_TEMPLATE.into(),
+            colors: true,
+            legend: true,
+            editor: false,
+        }
+    }
+}
+
+impl Html {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.css = theme.css().into();
+        self
+    }
+
+    /// Replaces the stylesheet with a custom one.
+    pub fn css(mut self, css: impl Into<Cow<'static, str>>) -> Self {
+        self.css = css.into();
+        self
+    }
+
+    /// Replaces the page template, see [`Html`] for the placeholders.
+    pub fn template(mut self, template: impl Into<Cow<'static, str>>) -> Self {
+        self.template = template.into();
+        self
+    }
+
+    /// Whether cells are filled with the color of their activity. Enabled by
+    /// default.
+    pub fn colors(mut self, colors: bool) -> Self {
+        self.colors = colors;
+        self
+    }
+
+    /// Whether `{{legend}}` is filled in. Enabled by default.
+    pub fn legend(mut self, legend: bool) -> Self {
+        self.legend = legend;
+        self
+    }
+
+    /// Whether the timetable can be edited in the browser: clicking a cell
+    /// books, renames or frees it, and dragging a booking moves it to a free
+    /// slot. The edited plan is downloaded as a plan file. The legend is not
+    /// updated. Disabled by default.
+    pub fn editor(mut self, editor: bool) -> Self {
+        self.editor = editor;
+        self
+    }
+
+    fn cell_style(&self, week_plan: &WeekPlan, activity: &str) -> String {
+        if !self.colors || activity.is_empty() {
+            return String::new();
+        }
+
+        let color = week_plan.color(activity);
+        format!(
+            " style=\"background-color: {color}; color: {}\"",
+            color.contrasting_text()
+        )
+    }
+
+    fn table(&self, week_plan: &WeekPlan) -> String {
+        let (weekdays, times, table) = week_plan.to_table();
+        let locale = &self.locale;
+
+        let mut html = String::new();
+        html.push_str("    <table class=\"schedule-table\">\n");
+        html.push_str("        <tr class=\"header-row\">\n");
+        html.push_str("            <th></th>\n");
+
+        // Add weekday headers
+        for &day in &weekdays {
+            html.push_str(&format!(
+                "            <th>{}</th>\n",
+                escape(locale.weekday(day))
+            ));
+        }
+        html.push_str("        </tr>\n");
+
+        // Add table rows
+        for (i, &time) in times.iter().enumerate() {
+            html.push_str("        <tr>\n");
+            html.push_str(&format!("            <th>{}</th>\n", locale.time(time)));
+
+            for (j, day) in weekdays.iter().enumerate() {
+                let activity = &table[i + j * times.len()];
+                // The slot of the cell, as written in plan files
+                let slot = if self.editor {
+                    format!(" data-weekday=\"{day}\" data-start=\"{time}\"")
+                } else {
+                    String::new()
+                };
+                html.push_str(&format!(
+                    "            <td{slot}{}>{}</td>\n",
+                    self.cell_style(week_plan, activity),
+                    escape(activity)
+                ));
+            }
+
+            html.push_str("        </tr>\n");
+        }
+
+        html.push_str("    </table>");
+        html
+    }
+
+    fn legend_table(&self, week_plan: &WeekPlan) -> String {
+        let activities = week_plan.activities();
+        if !self.legend || activities.is_empty() {
+            return String::new();
+        }
+
+        let locale = &self.locale;
+        let mut html = String::new();
+        html.push_str("    <table class=\"legend\">\n");
+        html.push_str(&format!(
+            "        <caption>{}</caption>\n",
+            escape(&locale.legend)
+        ));
+        html.push_str(&format!(
+            "        <tr><th>{}</th><th>{}</th></tr>\n",
+            escape(&locale.activity),
+            escape(&locale.hours)
+        ));
+
+        for (activity, minutes) in &activities {
+            html.push_str(&format!(
+                "        <tr><td><span class=\"swatch\" style=\"background-color: {}\"></span>{}</td><td>{}</td></tr>\n",
+                week_plan.color(activity),
+                escape(activity),
+                number(f64::from(*minutes) / 60.0)
+            ));
+        }
+
+        html.push_str("    </table>");
+        html
+    }
+
+    fn editor_script(&self, week_plan: &WeekPlan) -> String {
+        if !self.editor {
+            return String::new();
+        }
+
+        // `<` can't end the script early once escaped
+        let plan = serde_json::to_string(week_plan)
+            .expect("plans serialize to JSON")
+            .replace('<', "\\u003c");
+
+        format!(
+            "    <div id=\"editor\" data-colors=\"{}\" data-prompt=\"{}\">\n        \
+             <button type=\"button\" id=\"download\">{}</button>\n        \
+             <p id=\"editor-status\" role=\"status\"></p>\n    </div>\n    \
+             <script type=\"application/json\" id=\"week-plan\">{plan}</script>\n    \
+             <script>\n{}    </script>",
+            self.colors,
+            escape(&self.locale.activity),
+            escape(&self.locale.download),
+            EDITOR_SCRIPT
+        )
+    }
+
+    pub fn render_to_string(&self, week_plan: &WeekPlan) -> String {
+        let table = self.table(week_plan);
+        let legend = self.legend_table(week_plan);
+        let editor = self.editor_script(week_plan);
+
+        fill(
+            &self.template,
+            &[
+                ("lang", &escape(&self.locale.code)),
+                ("title", &escape(&self.locale.title)),
+                ("css", &self.css),
+                ("table", &table),
+                ("legend", &legend),
+                ("editor", &editor),
+            ],
+        )
+    }
+}
+
+/// Replaces the `{{name}}` placeholders of `template` in a single pass, so
+/// that placeholders appearing in the values are not expanded.
+fn fill(template: &str, values: &[(&str, &str)]) -> String {
+    let mut filled = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        filled.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let value = rest.find("}}").and_then(|end| {
+            let name = rest[2..end].trim();
+            let (_, value) = values.iter().find(|(key, _)| *key == name)?;
+            Some((value, end + 2))
+        });
+
+        match value {
+            Some((value, len)) => {
+                filled.push_str(value);
+                rest = &rest[len..];
+            }
+            None => {
+                filled.push_str("{{");
+                rest = &rest[2..];
+            }
+        }
+    }
+
+    filled.push_str(rest);
+    filled
+}
+
+impl Render for Html {
+    fn render(&self, week_plan: &WeekPlan, out: &mut dyn Write) -> io::Result<()> {
+        out.write_all(self.render_to_string(week_plan).as_bytes())
+    }
+}
+
+/// Escapes text for use in HTML content and attribute values.
+pub(crate) fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::weekplan::locale::Language;
+    use crate::weekplan::{Color, Time, Weekday};
+
+    #[test]
+    fn localized() {
+        let mut week_plan = WeekPlan::new(Time::new(8, 30).unwrap(), 90, 2).unwrap();
+        week_plan
+            .try_insert(Weekday::Monday, Time::new(8, 30).unwrap(), "R&D".into())
+            .unwrap();
+
+        let html = Html::new()
+            .locale(Language::Italian.into())
+            .render_to_string(&week_plan);
+
+        assert!(html.contains(r#"<html lang="it">"#));
+        assert!(html.contains("<title>Orario settimanale</title>"));
+        assert!(html.contains("<th>Lunedì</th>"));
+        assert!(html.contains(">R&amp;D</td>"));
+        assert!(html.contains("<caption>Legenda</caption>"));
+    }
+
+    #[test]
+    fn themes() {
+        let week_plan = WeekPlan::new(Time::new(8, 30).unwrap(), 90, 2).unwrap();
+
+        for theme in Theme::ALL {
+            assert_eq!(theme.name().parse(), Ok(theme));
+
+            let html = Html::new().theme(theme).render_to_string(&week_plan);
+            assert!(html.contains(theme.css()));
+        }
+
+        let html = Html::new()
+            .css("td { color: red; }")
+            .render_to_string(&week_plan);
+        assert!(html.contains("td { color: red; }"));
+        assert!(!html.contains(Theme::Light.css()));
+    }
+
+    #[test]
+    fn custom_template() {
+        let mut week_plan = WeekPlan::new(Time::new(8, 30).unwrap(), 90, 2).unwrap();
+        week_plan
+            .try_insert(
+                Weekday::Monday,
+                Time::new(8, 30).unwrap(),
+                "{{title}}".into(),
+            )
+            .unwrap();
+
+        let html = Html::new()
+            .template("<h1>{{ title }}</h1>{{unknown}}{{table}}")
+            .render_to_string(&week_plan);
+
+        assert!(html.starts_with("<h1>Weekly plan</h1>{{unknown}}    <table"));
+        assert!(html.contains(">{{title}}</td>"));
+    }
+
+    #[test]
+    fn colors_and_legend() {
+        let mut week_plan = WeekPlan::new(Time::new(8, 30).unwrap(), 90, 3).unwrap();
+        week_plan
+            .try_insert_range(
+                Weekday::Monday,
+                (Time::new(8, 30).unwrap(), 3),
+                "AAA".into(),
+            )
+            .unwrap()
+            .set_color("AAA", Color::rgb(0, 0, 128));
+
+        let html = Html::new().render_to_string(&week_plan);
+        assert!(html.contains(r#"<td style="background-color: #000080; color: #ffffff">AAA</td>"#));
+        assert!(html.contains(
+            r#"<span class="swatch" style="background-color: #000080"></span>AAA</td><td>4.5</td>"#
+        ));
+
+        let html = Html::new()
+            .colors(false)
+            .legend(false)
+            .render_to_string(&week_plan);
+        assert!(html.contains("<td>AAA</td>"));
+        assert!(!html.contains(r#"<table class="legend">"#));
+    }
+
+    #[test]
+    fn editor() {
+        let mut week_plan = WeekPlan::new(Time::new(8, 30).unwrap(), 90, 2).unwrap();
+        week_plan
+            .try_insert(
+                Weekday::Tuesday,
+                Time::new(10, 0).unwrap(),
+                "</script>".into(),
+            )
+            .unwrap();
+
+        let html = Html::new()
+            .locale(Language::Italian.into())
+            .render_to_string(&week_plan);
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("data-weekday"));
+
+        let html = Html::new()
+            .locale(Language::Italian.into())
+            .editor(true)
+            .render_to_string(&week_plan);
+        assert!(html.contains(r#"<td data-weekday="Monday" data-start="08:30">"#));
+        assert!(html.contains(r#"<button type="button" id="download">Scarica il piano</button>"#));
+        assert!(html.contains(EDITOR_SCRIPT));
+        assert_eq!(html.matches("</script>").count(), 2);
+
+        let start = html.find(r#"id="week-plan">"#).unwrap() + 15;
+        let end = start + html[start..].find("</script>").unwrap();
+        let plan: WeekPlan = serde_json::from_str(&html[start..end]).unwrap();
+        assert_eq!(plan, week_plan);
+
+        let html = Html::new()
+            .template("{{table}}")
+            .editor(true)
+            .render_to_string(&week_plan);
+        assert!(!html.contains("<script"));
+    }
+}
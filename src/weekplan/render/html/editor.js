@@ -0,0 +1,184 @@
+// Edits the timetable in place: clicking a cell books, renames or frees it,
+// dragging a booking moves it, and the download button saves the plan as a
+// plan file.
+(function () {
+    "use strict";
+
+    const WEEKDAYS = ["Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday", "Sunday"];
+
+    const editor = document.getElementById("editor");
+    const status = document.getElementById("editor-status");
+    const weekPlan = JSON.parse(document.getElementById("week-plan").textContent);
+    const colorsEnabled = editor.dataset.colors === "true";
+
+    function toMinutes(time) {
+        const [hour, minute] = time.split(":").map(Number);
+        return hour * 60 + minute;
+    }
+
+    // Mirrors `WeekPlan::is_valid_slot`.
+    function isValidSlot(time) {
+        const distance = toMinutes(time) - toMinutes(weekPlan.start);
+        if (distance < 0) {
+            return false;
+        }
+        const index = Math.floor(distance / weekPlan.slot_duration);
+        return distance % weekPlan.slot_duration === 0 && index < weekPlan.slots;
+    }
+
+    // Mirrors `Color::from_name`.
+    function colorFromName(name) {
+        let hash = 0x811c9dc5;
+        for (const byte of new TextEncoder().encode(name)) {
+            hash = Math.imul(hash ^ byte, 0x01000193) >>> 0;
+        }
+
+        const hue = hash % 360;
+        const saturation = 0.55;
+        const lightness = 0.75;
+        const chroma = (1 - Math.abs(2 * lightness - 1)) * saturation;
+        const sector = hue / 60;
+        const x = chroma * (1 - Math.abs((sector % 2) - 1));
+        const [r, g, b] = [
+            [chroma, x, 0],
+            [x, chroma, 0],
+            [0, chroma, x],
+            [0, x, chroma],
+            [x, 0, chroma],
+            [chroma, 0, x],
+        ][Math.min(Math.floor(sector), 5)];
+
+        const m = lightness - chroma / 2;
+        return [r, g, b].map((value) => Math.round((value + m) * 255));
+    }
+
+    function parseColor(color) {
+        let hex = color.trim().slice(1);
+        if (hex.length === 3) {
+            hex = hex.replace(/./g, "$&$&");
+        }
+        return [0, 2, 4].map((i) => parseInt(hex.slice(i, i + 2), 16));
+    }
+
+    // Mirrors `Color::contrasting_text`.
+    function contrastingText([r, g, b]) {
+        const linear = (channel) => {
+            const c = channel / 255;
+            return c <= 0.04045 ? c / 12.92 : Math.pow((c + 0.055) / 1.055, 2.4);
+        };
+        const luminance = 0.2126 * linear(r) + 0.7152 * linear(g) + 0.0722 * linear(b);
+        return luminance > 0.179 ? "#000000" : "#ffffff";
+    }
+
+    function key(cell) {
+        return cell.dataset.weekday + " " + cell.dataset.start;
+    }
+
+    function show(cell) {
+        const activity = weekPlan.plan[key(cell)] || "";
+        cell.textContent = activity;
+        cell.draggable = activity !== "";
+        cell.removeAttribute("style");
+        cell.style.cursor = "pointer";
+
+        if (colorsEnabled && activity !== "") {
+            const colors = weekPlan.colors || {};
+            const color = Object.hasOwn(colors, activity)
+                ? parseColor(colors[activity])
+                : colorFromName(activity);
+            const hex = "#" + color.map((c) => c.toString(16).padStart(2, "0")).join("");
+            cell.style.backgroundColor = hex;
+            cell.style.color = contrastingText(color);
+        }
+    }
+
+    function report(message) {
+        status.textContent = message;
+    }
+
+    function book(cell, activity) {
+        if (!isValidSlot(cell.dataset.start)) {
+            report("Invalid slot " + cell.dataset.start);
+            return false;
+        }
+        if (activity === "") {
+            delete weekPlan.plan[key(cell)];
+        } else {
+            weekPlan.plan[key(cell)] = activity;
+        }
+        show(cell);
+        return true;
+    }
+
+    const cells = document.querySelectorAll("td[data-weekday][data-start]");
+    let dragged = null;
+
+    for (const cell of cells) {
+        show(cell);
+
+        cell.addEventListener("click", () => {
+            const current = weekPlan.plan[key(cell)] || "";
+            const activity = prompt(editor.dataset.prompt, current);
+            if (activity !== null && book(cell, activity.trim())) {
+                report("");
+            }
+        });
+
+        cell.addEventListener("dragstart", (event) => {
+            dragged = cell;
+            event.dataTransfer.effectAllowed = "move";
+            event.dataTransfer.setData("text/plain", key(cell));
+        });
+
+        cell.addEventListener("dragover", (event) => {
+            if (dragged !== null) {
+                event.preventDefault();
+            }
+        });
+
+        cell.addEventListener("drop", (event) => {
+            event.preventDefault();
+            const from = dragged;
+            dragged = null;
+            if (from === null || from === cell) {
+                return;
+            }
+            if (key(cell) in weekPlan.plan) {
+                report("Slot " + key(cell) + " already booked");
+                return;
+            }
+
+            const activity = weekPlan.plan[key(from)];
+            if (book(cell, activity)) {
+                book(from, "");
+                report("");
+            }
+        });
+    }
+
+    // Writes the plan as `WeekPlan`'s `Serialize` does, bookings sorted by
+    // weekday and time.
+    function toJson() {
+        const plan = {};
+        const keys = Object.keys(weekPlan.plan).sort((a, b) => {
+            const [dayA, timeA] = a.split(" ");
+            const [dayB, timeB] = b.split(" ");
+            return WEEKDAYS.indexOf(dayA) - WEEKDAYS.indexOf(dayB)
+                || toMinutes(timeA) - toMinutes(timeB);
+        });
+        for (const slot of keys) {
+            plan[slot] = weekPlan.plan[slot];
+        }
+
+        return JSON.stringify({ ...weekPlan, plan }, null, 2) + "\n";
+    }
+
+    document.getElementById("download").addEventListener("click", () => {
+        const blob = new Blob([toJson()], { type: "application/json" });
+        const link = document.createElement("a");
+        link.href = URL.createObjectURL(blob);
+        link.download = "plan.json";
+        link.click();
+        URL.revokeObjectURL(link.href);
+    });
+})();
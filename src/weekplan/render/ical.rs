@@ -0,0 +1,320 @@
+use std::io::{self, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{merge_cells, Render};
+use crate::weekplan::locale::Locale;
+use crate::weekplan::WeekPlan;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Renders a plan as an iCalendar file with a weekly recurring event per
+/// booking, consecutive slots of the same activity being merged.
+///
+/// Events start in the week of [`ICalendar::week_of`], the current one by
+/// default, at floating times: calendar apps show them at the same hour in
+/// any time zone.
+///
+/// Event UIDs are made of the slot, a hash of the activity and the
+/// [`ICalendar::domain`], which sets calendars of different plans apart.
+#[derive(Debug, Clone)]
+pub struct ICalendar {
+    locale: Locale,
+    domain: String,
+    /// Days since 1970-01-01 of the Monday the events start from.
+    monday: i64,
+    /// Seconds since the epoch the file is created at.
+    created: u64,
+}
+
+impl Default for ICalendar {
+    fn default() -> Self {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |now| now.as_secs());
+        let today = i64::try_from(now / SECONDS_PER_DAY).unwrap_or_default();
+
+        ICalendar {
+            locale: Locale::default(),
+            domain: "weekly_planner".to_owned(),
+            monday: monday_of(today),
+            created: now,
+        }
+    }
+}
+
+impl ICalendar {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only the title of the locale is used, as the calendar name.
+    pub fn locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    /// Ends the UIDs of the events, `weekly_planner` by default. Plans
+    /// exported with the same domain share the UIDs of the bookings they have
+    /// in common, so give each plan its own, like `fall-2024.example.org`.
+    pub fn domain(mut self, domain: impl Into<String>) -> Self {
+        self.domain = domain.into();
+        self
+    }
+
+    /// Starts the events in the week of the given date, or returns `None` if
+    /// it doesn't exist.
+    pub fn week_of(mut self, year: i32, month: u8, day: u8) -> Option<Self> {
+        self.monday = monday_of(days_from_civil(year, month, day)?);
+        Some(self)
+    }
+
+    pub fn render_to_string(&self, week_plan: &WeekPlan) -> String {
+        let (weekdays, times, table) = week_plan.to_table();
+        let stamp = format!(
+            "{}T{}Z",
+            date(i64::try_from(self.created / SECONDS_PER_DAY).unwrap_or_default()),
+            clock(self.created % SECONDS_PER_DAY)
+        );
+
+        let mut lines = vec![
+            "BEGIN:VCALENDAR".to_owned(),
+            "VERSION:2.0".to_owned(),
+            "PRODID:-//weekly_planner//EN".to_owned(),
+            "CALSCALE:GREGORIAN".to_owned(),
+            format!("X-WR-CALNAME:{}", escape(&self.locale.title)),
+        ];
+
+        for cell in merge_cells(&table, times.len()) {
+            if cell.activity.is_empty() {
+                continue;
+            }
+
+            let weekday = weekdays[cell.day];
+            let day = date(self.monday + i64::from(weekday.iso_number() - 1));
+            let (from, to) = cell.interval(&times, week_plan.slot_duration());
+            // 24:00 is written as midnight of the next day
+            let end = if to.is_end_of_day() {
+                format!(
+                    "{}T000000",
+                    date(self.monday + i64::from(weekday.iso_number()))
+                )
+            } else {
+                format!("{day}T{}", clock(u64::from(to.to_minutes()) * 60))
+            };
+
+            lines.extend([
+                "BEGIN:VEVENT".to_owned(),
+                // Stable across weeks, so that subscribed calendars update
+                // events instead of duplicating them
+                format!(
+                    "UID:{}-{}-{:08x}@{}",
+                    weekday.to_string().to_lowercase(),
+                    from.format("HHmm"),
+                    fnv1a(cell.activity),
+                    self.domain
+                ),
+                format!("DTSTAMP:{stamp}"),
+                format!("DTSTART:{day}T{}", clock(u64::from(from.to_minutes()) * 60)),
+                format!("DTEND:{end}"),
+                "RRULE:FREQ=WEEKLY".to_owned(),
+                format!("SUMMARY:{}", escape(cell.activity)),
+                "END:VEVENT".to_owned(),
+            ]);
+        }
+        lines.push("END:VCALENDAR".to_owned());
+
+        lines.iter().map(|line| fold(line)).collect()
+    }
+}
+
+impl Render for ICalendar {
+    fn render(&self, week_plan: &WeekPlan, out: &mut dyn Write) -> io::Result<()> {
+        out.write_all(self.render_to_string(week_plan).as_bytes())
+    }
+}
+
+/// The 32-bit FNV-1a hash of `text`, the same in every build.
+fn fnv1a(text: &str) -> u32 {
+    text.bytes().fold(0x811c9dc5, |hash, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x01000193)
+    })
+}
+
+/// Escapes text for a property value.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Ends a content line with CRLF, splitting it in lines of at most 75 bytes
+/// continued by a leading space.
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + 2);
+    let mut len = 0;
+    for c in line.chars() {
+        if len + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            len = 1;
+        }
+        folded.push(c);
+        len += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+/// The Monday of the week of a day, both counted from 1970-01-01.
+fn monday_of(days: i64) -> i64 {
+    // 1970-01-01 was a Thursday
+    days - (days + 3).rem_euclid(7)
+}
+
+/// Days since 1970-01-01 of a date of the proleptic Gregorian calendar.
+fn days_from_civil(year: i32, month: u8, day: u8) -> Option<i64> {
+    let leap = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+    let days_in_month = match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        1..=12 => 31,
+        _ => return None,
+    };
+    if day == 0 || day > days_in_month {
+        return None;
+    }
+
+    // Counts years from March, so that leap days end the year.
+    let year = i64::from(year) - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = i64::from(month);
+    let month = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * month + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    Some(era * 146097 + day_of_era - 719468)
+}
+
+/// Formats days since 1970-01-01 as `YYYYMMDD`.
+fn date(days: i64) -> String {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!("{year:04}{month:02}{day:02}")
+}
+
+/// Formats seconds since midnight as `HHMMSS`.
+fn clock(seconds: u64) -> String {
+    format!(
+        "{:02}{:02}{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::weekplan::{Time, Weekday};
+
+    #[test]
+    fn events() {
+        let mut week_plan = WeekPlan::new(Time::new(21, 0).unwrap(), 90, 2).unwrap();
+        week_plan
+            .try_insert_range(
+                Weekday::Wednesday,
+                (Time::new(21, 0).unwrap(), 2),
+                "Lab; night, shift".into(),
+            )
+            .unwrap()
+            .try_insert(Weekday::Sunday, Time::new(22, 30).unwrap(), "B".into())
+            .unwrap();
+
+        let ical = ICalendar::new()
+            .week_of(2024, 2, 29)
+            .unwrap()
+            .render_to_string(&week_plan);
+
+        assert!(ical.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ical.ends_with("END:VCALENDAR\r\n"));
+        assert!(!ical.replace("\r\n", "").contains('\n'));
+        assert_eq!(ical.matches("BEGIN:VEVENT").count(), 2);
+        let uid = format!(
+            "UID:wednesday-2100-{:08x}@weekly_planner\r\n",
+            fnv1a("Lab; night, shift")
+        );
+        assert!(ical.contains(&uid));
+        assert!(ical.contains(
+            "DTSTART:20240228T210000\r\nDTEND:20240229T000000\r\nRRULE:FREQ=WEEKLY\r\n\
+             SUMMARY:Lab\\; night\\, shift\r\n"
+        ));
+        assert!(ical.contains("DTSTART:20240303T223000\r\nDTEND:20240304T000000\r\n"));
+    }
+
+    #[test]
+    fn uids() {
+        let uids = |activity: &str, calendar: ICalendar| {
+            let mut week_plan = WeekPlan::new(Time::new(8, 30).unwrap(), 90, 1).unwrap();
+            week_plan
+                .try_insert(Weekday::Monday, Time::new(8, 30).unwrap(), activity.into())
+                .unwrap();
+            let ical = calendar.render_to_string(&week_plan);
+            ical.lines()
+                .find_map(|line| line.strip_prefix("UID:"))
+                .unwrap()
+                .to_owned()
+        };
+
+        let uid = uids("A", ICalendar::new());
+        assert!(uid.starts_with("monday-0830-"));
+        assert_eq!(
+            uid,
+            uids("A", ICalendar::new().week_of(2024, 9, 16).unwrap())
+        );
+        assert_ne!(uid, uids("B", ICalendar::new()));
+        assert_ne!(
+            uid,
+            uids("A", ICalendar::new().domain("fall-2024.example.org"))
+        );
+        assert!(uids("A", ICalendar::new().domain("fall-2024.example.org"))
+            .ends_with("@fall-2024.example.org"));
+    }
+
+    #[test]
+    fn dates() {
+        assert_eq!(days_from_civil(1970, 1, 1), Some(0));
+        assert_eq!(days_from_civil(2000, 3, 1), Some(11017));
+        assert_eq!(days_from_civil(2023, 2, 29), None);
+        assert_eq!(date(11017), "20000301");
+        assert_eq!(date(days_from_civil(2024, 2, 29).unwrap()), "20240229");
+        assert_eq!(date(monday_of(0)), "19691229");
+        assert_eq!(clock(45296), "123456");
+    }
+
+    #[test]
+    fn folding() {
+        let line = "SUMMARY:".to_owned() + &"è".repeat(40);
+        let folded = fold(&line);
+        assert!(folded.split("\r\n").all(|line| line.len() <= 75));
+        assert_eq!(folded.replace("\r\n ", ""), line + "\r\n");
+    }
+}
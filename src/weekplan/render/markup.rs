@@ -0,0 +1,306 @@
+//! Lightweight markup formats for wikis: Markdown and AsciiDoc.
+
+use std::io::{self, Write};
+
+use super::{merge_cells, Render};
+use crate::weekplan::locale::Locale;
+use crate::weekplan::WeekPlan;
+
+/// How [`Markdown`] and [`AsciiDoc`] lay out a plan.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Layout {
+    /// A table with a row per slot and a column per day.
+    #[default]
+    Grid,
+    /// A section per day listing its bookings, consecutive slots of the same
+    /// activity being merged.
+    Agenda,
+}
+
+/// The syntax that differs between the markup languages.
+trait Syntax {
+    fn title(&self, title: &str) -> String;
+    fn heading(&self, heading: &str) -> String;
+    fn bullet(&self, item: &str) -> String;
+    fn table(&self, header: &[String], rows: &[Vec<String>]) -> String;
+    fn escape(&self, text: &str) -> String;
+}
+
+/// Renders a plan as GitHub-flavored Markdown.
+#[derive(Debug, Clone, Default)]
+pub struct Markdown {
+    locale: Locale,
+    layout: Layout,
+}
+
+impl Markdown {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    pub fn layout(mut self, layout: Layout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    pub fn render_to_string(&self, week_plan: &WeekPlan) -> String {
+        markup(self, &self.locale, self.layout, week_plan)
+    }
+}
+
+impl Syntax for Markdown {
+    fn title(&self, title: &str) -> String {
+        format!("# {title}\n")
+    }
+
+    fn heading(&self, heading: &str) -> String {
+        format!("## {heading}\n")
+    }
+
+    fn bullet(&self, item: &str) -> String {
+        format!("- {item}\n")
+    }
+
+    fn table(&self, header: &[String], rows: &[Vec<String>]) -> String {
+        let row = |cells: &[String]| format!("| {} |\n", cells.join(" | "));
+
+        let mut table = row(header);
+        table.push_str(&row(&vec!["---".to_owned(); header.len()]));
+        for cells in rows {
+            table.push_str(&row(cells));
+        }
+        table
+    }
+
+    fn escape(&self, text: &str) -> String {
+        let mut escaped = String::with_capacity(text.len());
+        for c in text.chars() {
+            if matches!(
+                c,
+                '\\' | '|' | '*' | '_' | '`' | '[' | ']' | '<' | '>' | '#'
+            ) {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+        }
+        escaped
+    }
+}
+
+impl Render for Markdown {
+    fn render(&self, week_plan: &WeekPlan, out: &mut dyn Write) -> io::Result<()> {
+        out.write_all(self.render_to_string(week_plan).as_bytes())
+    }
+}
+
+/// Renders a plan as AsciiDoc.
+#[derive(Debug, Clone, Default)]
+pub struct AsciiDoc {
+    locale: Locale,
+    layout: Layout,
+}
+
+impl AsciiDoc {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    pub fn layout(mut self, layout: Layout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    pub fn render_to_string(&self, week_plan: &WeekPlan) -> String {
+        markup(self, &self.locale, self.layout, week_plan)
+    }
+}
+
+impl Syntax for AsciiDoc {
+    fn title(&self, title: &str) -> String {
+        format!("= {title}\n")
+    }
+
+    fn heading(&self, heading: &str) -> String {
+        format!("== {heading}\n")
+    }
+
+    fn bullet(&self, item: &str) -> String {
+        format!("* {item}\n")
+    }
+
+    fn table(&self, header: &[String], rows: &[Vec<String>]) -> String {
+        let row = |cells: &[String]| {
+            let cells: Vec<String> = cells.iter().map(|cell| format!("|{cell}")).collect();
+            format!("{}\n", cells.join(" ").trim_end())
+        };
+
+        let mut table = format!(
+            "[cols=\"1,{}*\",options=\"header\"]\n|===\n",
+            header.len() - 1
+        );
+        table.push_str(&row(header));
+        for cells in rows {
+            table.push_str(&row(cells));
+        }
+        table.push_str("|===\n");
+        table
+    }
+
+    fn escape(&self, text: &str) -> String {
+        // Formatting marks become character references, which AsciiDoc
+        // prints literally; the cell separator needs a backslash instead.
+        let mut escaped = String::with_capacity(text.len());
+        for c in text.chars() {
+            match c {
+                '|' => escaped.push_str("\\|"),
+                '*' | '_' | '`' | '#' | '^' | '~' | '+' | '[' | ']' | '{' | '}' => {
+                    escaped.push_str(&format!("&#{};", u32::from(c)));
+                }
+                '<' => escaped.push_str("&lt;"),
+                '>' => escaped.push_str("&gt;"),
+                '&' => escaped.push_str("&amp;"),
+                c => escaped.push(c),
+            }
+        }
+        escaped
+    }
+}
+
+impl Render for AsciiDoc {
+    fn render(&self, week_plan: &WeekPlan, out: &mut dyn Write) -> io::Result<()> {
+        out.write_all(self.render_to_string(week_plan).as_bytes())
+    }
+}
+
+fn markup(syntax: &impl Syntax, locale: &Locale, layout: Layout, week_plan: &WeekPlan) -> String {
+    let (weekdays, times, table) = week_plan.to_table();
+
+    let mut out = syntax.title(&syntax.escape(&locale.title));
+    out.push('\n');
+
+    match layout {
+        Layout::Grid => {
+            let header: Vec<String> = std::iter::once(String::new())
+                .chain(
+                    weekdays
+                        .iter()
+                        .map(|&day| syntax.escape(locale.weekday(day))),
+                )
+                .collect();
+
+            let rows: Vec<Vec<String>> = times
+                .iter()
+                .enumerate()
+                .map(|(slot, &time)| {
+                    std::iter::once(locale.time(time).to_string())
+                        .chain(
+                            (0..weekdays.len())
+                                .map(|day| syntax.escape(&table[day * times.len() + slot])),
+                        )
+                        .collect()
+                })
+                .collect();
+
+            out.push_str(&syntax.table(&header, &rows));
+        }
+        Layout::Agenda => {
+            let duration = week_plan.slot_duration();
+            let cells = merge_cells(&table, times.len());
+
+            for (day, &weekday) in weekdays.iter().enumerate() {
+                let bookings: Vec<_> = cells
+                    .iter()
+                    .filter(|cell| cell.day == day && !cell.activity.is_empty())
+                    .collect();
+                if bookings.is_empty() {
+                    continue;
+                }
+
+                out.push_str(&syntax.heading(&syntax.escape(locale.weekday(weekday))));
+                out.push('\n');
+                for cell in bookings {
+                    let (from, to) = cell.interval(&times, duration);
+                    out.push_str(&syntax.bullet(&format!(
+                        "{}–{} {}",
+                        locale.time(from),
+                        locale.time(to),
+                        syntax.escape(cell.activity)
+                    )));
+                }
+                out.push('\n');
+            }
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::weekplan::{Time, Weekday};
+
+    fn week_plan() -> WeekPlan {
+        let mut week_plan = WeekPlan::new(Time::new(8, 30).unwrap(), 90, 3).unwrap();
+        week_plan
+            .try_insert_range(
+                Weekday::Monday,
+                (Time::new(8, 30).unwrap(), 2),
+                "Lab | A".into(),
+            )
+            .unwrap()
+            .try_insert(Weekday::Wednesday, Time::new(11, 30).unwrap(), "C_D".into())
+            .unwrap();
+        week_plan
+    }
+
+    #[test]
+    fn markdown() {
+        let grid = Markdown::new().render_to_string(&week_plan());
+        let lines: Vec<&str> = grid.lines().collect();
+        assert_eq!(lines[0], "# Weekly plan");
+        assert!(lines[2].starts_with("|  | Monday | Tuesday | Wednesday |"));
+        assert!(lines[3].starts_with("| --- | --- |"));
+        assert!(lines[4].starts_with(r"| 08:30 | Lab \| A |  |  |"));
+        assert!(lines[6].starts_with(r"| 11:30 |  |  | C\_D |"));
+        assert_eq!(lines.len(), 7);
+
+        let agenda = Markdown::new()
+            .layout(Layout::Agenda)
+            .render_to_string(&week_plan());
+        assert_eq!(
+            agenda,
+            "# Weekly plan\n\n\
+             ## Monday\n\n- 08:30–11:30 Lab \\| A\n\n\
+             ## Wednesday\n\n- 11:30–13:00 C\\_D\n\n"
+        );
+    }
+
+    #[test]
+    fn asciidoc() {
+        let grid = AsciiDoc::new().render_to_string(&week_plan());
+        let lines: Vec<&str> = grid.lines().collect();
+        assert_eq!(lines[0], "= Weekly plan");
+        assert_eq!(lines[2], r#"[cols="1,7*",options="header"]"#);
+        assert_eq!(lines[3], "|===");
+        assert!(lines[4].starts_with("| |Monday |Tuesday |Wednesday"));
+        assert_eq!(lines[5], r"|08:30 |Lab \| A | | | | | |");
+        assert_eq!(lines[7], r"|11:30 | | |C&#95;D | | | |");
+        assert_eq!(lines[8], "|===");
+
+        let agenda = AsciiDoc::new()
+            .layout(Layout::Agenda)
+            .render_to_string(&week_plan());
+        assert!(agenda.contains("== Monday\n\n* 08:30–11:30 Lab \\| A\n"));
+    }
+}
@@ -0,0 +1,401 @@
+use std::fmt::{self, Write as _};
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use super::text::{wrap, Font};
+use super::{merge_cells, number, Render};
+use crate::weekplan::locale::Locale;
+use crate::weekplan::{Color, WeekPlan};
+
+/// A landscape paper size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PageSize {
+    #[default]
+    A4,
+    Letter,
+}
+
+impl PageSize {
+    /// Width and height in points, in landscape orientation.
+    pub fn dimensions(&self) -> (f64, f64) {
+        match self {
+            PageSize::A4 => (841.89, 595.28),
+            PageSize::Letter => (792.0, 612.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsePageSizeError(String);
+
+impl fmt::Display for ParsePageSizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown page size {:?}, expected a4 or letter", self.0)
+    }
+}
+
+impl std::error::Error for ParsePageSizeError {}
+
+impl FromStr for PageSize {
+    type Err = ParsePageSizeError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "a4" => Ok(PageSize::A4),
+            "letter" => Ok(PageSize::Letter),
+            _ => Err(ParsePageSizeError(s.to_owned())),
+        }
+    }
+}
+
+const MARGIN: f64 = 36.0;
+const TITLE_SIZE: f64 = 16.0;
+const FONT_SIZE: f64 = 9.0;
+const LINE_HEIGHT: f64 = 11.0;
+const PADDING: f64 = 4.0;
+const TIME_COLUMN: f64 = 50.0;
+const HEADER_ROW: f64 = 22.0;
+
+const HEADER_FILL: Color = Color::rgb(0x00, 0x98, 0x79);
+const HEADER_CORNER_FILL: Color = Color::rgb(0x00, 0x79, 0x65);
+const GRID: Color = Color::rgb(0xdd, 0xdd, 0xdd);
+const TEXT: Color = Color::rgb(0x33, 0x33, 0x33);
+
+/// Renders a plan as a single landscape PDF page, using the standard
+/// Helvetica font so nothing needs to be embedded.
+#[derive(Debug, Clone)]
+pub struct Pdf {
+    locale: Locale,
+    page_size: PageSize,
+    colors: bool,
+}
+
+impl Default for Pdf {
+    fn default() -> Self {
+        Pdf {
+            locale: Locale::default(),
+            page_size: PageSize::default(),
+            colors: true,
+        }
+    }
+}
+
+impl Pdf {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    pub fn page_size(mut self, page_size: PageSize) -> Self {
+        self.page_size = page_size;
+        self
+    }
+
+    /// Whether cells are filled with the color of their activity. Enabled by
+    /// default.
+    pub fn colors(mut self, colors: bool) -> Self {
+        self.colors = colors;
+        self
+    }
+
+    fn content(&self, week_plan: &WeekPlan) -> String {
+        let (weekdays, times, table) = week_plan.to_table();
+        let (width, height) = self.page_size.dimensions();
+        let mut page = Page {
+            height,
+            content: String::new(),
+        };
+
+        page.text(
+            &self.locale.title,
+            Font::Bold,
+            TITLE_SIZE,
+            MARGIN,
+            MARGIN,
+            TEXT,
+        );
+
+        let top = MARGIN + TITLE_SIZE + 12.0;
+        let column = (width - 2.0 * MARGIN - TIME_COLUMN) / weekdays.len() as f64;
+        let row = (height - top - MARGIN - HEADER_ROW) / times.len().max(1) as f64;
+        let grid_top = top + HEADER_ROW;
+
+        // Header row
+        page.rect(
+            MARGIN,
+            top,
+            TIME_COLUMN,
+            HEADER_ROW,
+            Some(HEADER_CORNER_FILL),
+        );
+        for (day, &weekday) in weekdays.iter().enumerate() {
+            let x = MARGIN + TIME_COLUMN + day as f64 * column;
+            page.rect(x, top, column, HEADER_ROW, Some(HEADER_FILL));
+            page.centered(
+                &self.locale.weekday(weekday).to_uppercase(),
+                Font::Bold,
+                (x, top, column, HEADER_ROW),
+                Color::WHITE,
+            );
+        }
+
+        // Time column
+        for (slot, &time) in times.iter().enumerate() {
+            let y = grid_top + slot as f64 * row;
+            page.rect(MARGIN, y, TIME_COLUMN, row, Some(HEADER_FILL));
+            page.centered(
+                &self.locale.time(time).to_string(),
+                Font::Bold,
+                (MARGIN, y, TIME_COLUMN, row),
+                Color::WHITE,
+            );
+        }
+
+        // Bookings
+        for cell in merge_cells(&table, times.len()) {
+            let x = MARGIN + TIME_COLUMN + cell.day as f64 * column;
+            let y = grid_top + cell.slot as f64 * row;
+            let h = cell.span as f64 * row;
+
+            let fill =
+                (self.colors && !cell.activity.is_empty()).then(|| week_plan.color(cell.activity));
+            page.rect(x, y, column, h, fill.or(Some(Color::WHITE)));
+            page.stroke(x, y, column, h, GRID);
+            page.centered(
+                cell.activity,
+                Font::Regular,
+                (x, y, column, h),
+                fill.map_or(TEXT, |fill| fill.contrasting_text()),
+            );
+        }
+
+        page.content
+    }
+
+    fn document(&self, week_plan: &WeekPlan) -> Vec<u8> {
+        let (width, height) = self.page_size.dimensions();
+        let content = self.content(week_plan);
+
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>".to_owned(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_owned(),
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+                 /Resources << /Font << /F1 5 0 R /F2 6 0 R >> >> /Contents 4 0 R >>",
+                number(width),
+                number(height)
+            ),
+            format!(
+                "<< /Length {} >>\nstream\n{content}endstream",
+                content.len()
+            ),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+                .to_owned(),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>"
+                .to_owned(),
+            format!("<< /Title {} /Producer (weekly_planner) >>", string(&self.locale.title)),
+        ];
+
+        let mut pdf = String::from("%PDF-1.4\n");
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            let _ = write!(pdf, "{} 0 obj\n{object}\nendobj\n", i + 1);
+        }
+
+        let xref = pdf.len();
+        let _ = write!(pdf, "xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+        for offset in offsets {
+            let _ = writeln!(pdf, "{offset:010} 00000 n ");
+        }
+        let _ = write!(
+            pdf,
+            "trailer\n<< /Size {} /Root 1 0 R /Info {} 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+            objects.len() + 1,
+            objects.len()
+        );
+
+        pdf.into_bytes()
+    }
+}
+
+impl Render for Pdf {
+    fn render(&self, week_plan: &WeekPlan, out: &mut dyn Write) -> io::Result<()> {
+        out.write_all(&self.document(week_plan))
+    }
+}
+
+fn font_name(font: Font) -> &'static str {
+    match font {
+        Font::Regular => "F1",
+        Font::Bold => "F2",
+    }
+}
+
+/// A content stream, drawn in a top-down coordinate system.
+struct Page {
+    height: f64,
+    content: String,
+}
+
+impl Page {
+    fn rect(&mut self, x: f64, y: f64, w: f64, h: f64, fill: Option<Color>) {
+        if let Some(fill) = fill {
+            let _ = writeln!(
+                self.content,
+                "{} rg {} {} {} {} re f",
+                rgb(fill),
+                number(x),
+                number(self.height - y - h),
+                number(w),
+                number(h)
+            );
+        }
+    }
+
+    fn stroke(&mut self, x: f64, y: f64, w: f64, h: f64, color: Color) {
+        let _ = writeln!(
+            self.content,
+            "0.5 w {} RG {} {} {} {} re S",
+            rgb(color),
+            number(x),
+            number(self.height - y - h),
+            number(w),
+            number(h)
+        );
+    }
+
+    /// Draws a line of text with its top-left corner at `x`, `y`.
+    fn text(&mut self, text: &str, font: Font, size: f64, x: f64, y: f64, color: Color) {
+        let _ = writeln!(
+            self.content,
+            "BT /{} {} Tf {} rg {} {} Td {} Tj ET",
+            font_name(font),
+            number(size),
+            rgb(color),
+            number(x),
+            number(self.height - y - size * 0.8),
+            string(text)
+        );
+    }
+
+    /// Draws `text` wrapped and centered in a box, truncating it with an
+    /// ellipsis if it doesn't fit.
+    fn centered(&mut self, text: &str, font: Font, area: (f64, f64, f64, f64), color: Color) {
+        let (x, y, w, h) = area;
+        let max_lines = (((h - PADDING) / LINE_HEIGHT).floor() as usize).max(1);
+        let lines = wrap(text, font, FONT_SIZE, w - 2.0 * PADDING, max_lines);
+
+        let block = lines.len() as f64 * LINE_HEIGHT;
+        let mut line_y = y + (h - block) / 2.0 + (LINE_HEIGHT - FONT_SIZE) / 2.0;
+        for line in lines {
+            let line_x = x + (w - font.width(&line, FONT_SIZE)) / 2.0;
+            self.text(&line, font, FONT_SIZE, line_x, line_y, color);
+            line_y += LINE_HEIGHT;
+        }
+    }
+}
+
+fn rgb(color: Color) -> String {
+    let channel = |c: u8| number(f64::from(c) / 255.0);
+    format!(
+        "{} {} {}",
+        channel(color.r),
+        channel(color.g),
+        channel(color.b)
+    )
+}
+
+/// Encodes text as a PDF string in WinAnsiEncoding. Characters outside of
+/// Latin-1 are replaced with `?`.
+fn string(text: &str) -> String {
+    let mut encoded = String::from("(");
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                encoded.push('\\');
+                encoded.push(c);
+            }
+            ' '..='~' => encoded.push(c),
+            '\u{a0}'..='\u{ff}' => {
+                let _ = write!(encoded, "\\{:03o}", c as u32);
+            }
+            _ => encoded.push('?'),
+        }
+    }
+    encoded.push(')');
+    encoded
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::weekplan::{Time, Weekday};
+
+    fn week_plan() -> WeekPlan {
+        let mut week_plan = WeekPlan::new(Time::new(8, 30).unwrap(), 90, 4).unwrap();
+        week_plan
+            .try_insert_range(
+                Weekday::Monday,
+                (Time::new(8, 30).unwrap(), 2),
+                "Operating (systems)".into(),
+            )
+            .unwrap();
+        week_plan
+    }
+
+    #[test]
+    fn document_structure() {
+        let pdf = Pdf::new().render_to_vec(&week_plan());
+        let pdf = String::from_utf8(pdf).unwrap();
+
+        assert!(pdf.starts_with("%PDF-1.4\n"));
+        assert!(pdf.ends_with("%%EOF\n"));
+        assert!(pdf.contains("/MediaBox [0 0 841.89 595.28]"));
+
+        // Every xref entry points at the start of its object.
+        let xref = pdf.rfind("\nxref\n").unwrap() + 1;
+        let startxref: usize = pdf
+            .rsplit("startxref\n")
+            .next()
+            .unwrap()
+            .lines()
+            .next()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert_eq!(startxref, xref);
+
+        let entries = pdf[xref..]
+            .lines()
+            .skip(3)
+            .take_while(|line| line.ends_with(" n "));
+        for (i, entry) in entries.enumerate() {
+            let offset: usize = entry[..10].parse().unwrap();
+            assert!(pdf[offset..].starts_with(&format!("{} 0 obj", i + 1)));
+        }
+    }
+
+    #[test]
+    fn merged_cells() {
+        let pdf = Pdf::new()
+            .page_size(PageSize::Letter)
+            .render_to_vec(&week_plan());
+        let pdf = String::from_utf8(pdf).unwrap();
+
+        assert!(pdf.contains("/MediaBox [0 0 792 612]"));
+        // The two booked slots are merged into a single cell with one label.
+        assert_eq!(pdf.matches("(Operating \\(systems\\)) Tj").count(), 1);
+    }
+
+    #[test]
+    fn encoding() {
+        assert_eq!(string("Lunedì (1)"), "(Luned\\354 \\(1\\))");
+        assert_eq!(string("→"), "(?)");
+        assert_eq!(number(12.0), "12");
+        assert_eq!(number(0.5), "0.5");
+    }
+}
@@ -0,0 +1,298 @@
+use std::fmt::Write as _;
+use std::io::{self, Write};
+
+use super::html::escape;
+use super::text::{wrap, Font};
+use super::{merge_cells, number, Render};
+use crate::weekplan::locale::Locale;
+use crate::weekplan::{Color, Time, WeekPlan};
+
+const MARGIN: f64 = 20.0;
+const TITLE_ROW: f64 = 36.0;
+const HEADER_ROW: f64 = 28.0;
+const AXIS_WIDTH: f64 = 56.0;
+const FONT_SIZE: f64 = 12.0;
+const LINE_HEIGHT: f64 = 14.0;
+const PADDING: f64 = 4.0;
+
+const HEADER_FILL: Color = Color::rgb(0x00, 0x98, 0x79);
+const PLAIN_FILL: Color = Color::rgb(0xe0, 0xf2, 0xef);
+
+const STYLE: &str = "text { font-family: Helvetica, Arial, sans-serif; fill: #333333; }
+    .title { font-size: 18px; font-weight: bold; }
+    .day { font-size: 12px; font-weight: bold; fill: #ffffff; text-transform: uppercase; }
+    .hour { font-size: 10px; }
+    .hour-line { stroke: #dddddd; stroke-dasharray: 4 4; }
+    .day-line { stroke: #eeeeee; }
+    .time { font-size: 10px; }
+    .activity { font-size: 12px; }";
+
+/// Renders a plan as an SVG image with a proportional time axis: the height
+/// of a booking is proportional to its duration and free slots are left as
+/// gaps.
+#[derive(Debug, Clone)]
+pub struct Svg {
+    locale: Locale,
+    colors: bool,
+    scale: f64,
+    column_width: f64,
+}
+
+impl Default for Svg {
+    fn default() -> Self {
+        Svg {
+            locale: Locale::default(),
+            colors: true,
+            scale: 1.0,
+            column_width: 140.0,
+        }
+    }
+}
+
+impl Svg {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    /// Whether bookings are filled with the color of their activity. Enabled
+    /// by default.
+    pub fn colors(mut self, colors: bool) -> Self {
+        self.colors = colors;
+        self
+    }
+
+    /// Pixels per minute on the time axis, 1 by default.
+    pub fn scale(mut self, scale: f64) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Width of a day column in pixels, 140 by default.
+    pub fn column_width(mut self, column_width: f64) -> Self {
+        self.column_width = column_width;
+        self
+    }
+
+    pub fn render_to_string(&self, week_plan: &WeekPlan) -> String {
+        let (weekdays, times, table) = week_plan.to_table();
+        let locale = &self.locale;
+        let duration = week_plan.slot_duration();
+
+        let start = times.first().map_or(0, Time::to_minutes);
+        let minutes = u16::try_from(times.len()).unwrap_or(u16::MAX) * duration;
+        let end = start + minutes;
+
+        let grid_left = MARGIN + AXIS_WIDTH;
+        let grid_top = MARGIN + TITLE_ROW + HEADER_ROW;
+        let grid_width = weekdays.len() as f64 * self.column_width;
+        let grid_height = f64::from(minutes) * self.scale;
+        let width = grid_left + grid_width + MARGIN;
+        let height = grid_top + grid_height + MARGIN;
+        let y_of = |minute: u16| grid_top + f64::from(minute - start) * self.scale;
+
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" xml:lang="{}">"#,
+            escape(&locale.code),
+            w = number(width),
+            h = number(height),
+        );
+        let _ = writeln!(svg, "  <title>{}</title>", escape(&locale.title));
+        let _ = writeln!(svg, "  <style>\n    {STYLE}\n  </style>");
+        let _ = writeln!(
+            svg,
+            r#"  <rect width="100%" height="100%" fill="{}"/>"#,
+            Color::WHITE
+        );
+        let _ = writeln!(
+            svg,
+            r#"  <text class="title" x="{}" y="{}">{}</text>"#,
+            number(MARGIN),
+            number(MARGIN + 18.0),
+            escape(&locale.title)
+        );
+
+        // Day headers and column separators
+        for (day, &weekday) in weekdays.iter().enumerate() {
+            let x = grid_left + day as f64 * self.column_width;
+            let _ = writeln!(
+                svg,
+                r#"  <rect x="{}" y="{}" width="{}" height="{}" fill="{HEADER_FILL}"/>"#,
+                number(x),
+                number(MARGIN + TITLE_ROW),
+                number(self.column_width),
+                number(HEADER_ROW)
+            );
+            let _ = writeln!(
+                svg,
+                r#"  <text class="day" x="{}" y="{}" text-anchor="middle">{}</text>"#,
+                number(x + self.column_width / 2.0),
+                number(MARGIN + TITLE_ROW + HEADER_ROW / 2.0 + 4.0),
+                escape(locale.weekday(weekday))
+            );
+            let _ = writeln!(
+                svg,
+                r#"  <line class="day-line" x1="{x}" y1="{}" x2="{x}" y2="{}"/>"#,
+                number(grid_top),
+                number(grid_top + grid_height),
+                x = number(x + self.column_width),
+            );
+        }
+
+        // Hour ticks
+        let first_hour = start.div_ceil(60) * 60;
+        for minute in (first_hour..=end).step_by(60) {
+            let y = y_of(minute);
+            let _ = writeln!(
+                svg,
+                r#"  <line class="hour-line" x1="{}" y1="{y}" x2="{}" y2="{y}"/>"#,
+                number(grid_left),
+                number(grid_left + grid_width),
+                y = number(y),
+            );
+            if let Some(time) = Time::from_minutes(minute) {
+                let _ = writeln!(
+                    svg,
+                    r#"  <text class="hour" x="{}" y="{}" text-anchor="end">{}</text>"#,
+                    number(grid_left - 6.0),
+                    number(y + 3.5),
+                    locale.time(time)
+                );
+            }
+        }
+
+        // Bookings
+        for cell in merge_cells(&table, times.len()) {
+            if cell.activity.is_empty() {
+                continue;
+            }
+
+            let (from, to) = cell.interval(&times, duration);
+
+            let x = grid_left + cell.day as f64 * self.column_width + 2.0;
+            let y = y_of(from.to_minutes()) + 1.0;
+            let w = self.column_width - 4.0;
+            let h = f64::from(from.minutes_until(to).unwrap_or(0)) * self.scale - 2.0;
+
+            let fill = if self.colors {
+                week_plan.color(cell.activity)
+            } else {
+                PLAIN_FILL
+            };
+            let text = fill.contrasting_text();
+            let range = format!("{}–{}", locale.time(from), locale.time(to));
+
+            let _ = writeln!(svg, "  <g>");
+            let _ = writeln!(
+                svg,
+                "    <title>{} {range} {}</title>",
+                escape(locale.weekday(weekdays[cell.day])),
+                escape(cell.activity)
+            );
+            let _ = writeln!(
+                svg,
+                r#"    <rect x="{}" y="{}" width="{}" height="{}" rx="4" fill="{fill}"/>"#,
+                number(x),
+                number(y),
+                number(w),
+                number(h)
+            );
+
+            let max_lines = ((h - 2.0 * PADDING) / LINE_HEIGHT).floor() as usize;
+            let mut lines = vec![(range, "time")];
+            lines.extend(
+                wrap(
+                    cell.activity,
+                    Font::Regular,
+                    FONT_SIZE,
+                    w - 2.0 * PADDING,
+                    max_lines.saturating_sub(1).max(1),
+                )
+                .into_iter()
+                .map(|line| (line, "activity")),
+            );
+            if max_lines < 2 {
+                lines.remove(0);
+            }
+
+            let block = lines.len() as f64 * LINE_HEIGHT;
+            let mut line_y = y + (h - block) / 2.0 + LINE_HEIGHT - 3.0;
+            for (line, class) in lines {
+                let _ = writeln!(
+                    svg,
+                    r#"    <text class="{class}" x="{}" y="{}" text-anchor="middle" style="fill: {text}">{}</text>"#,
+                    number(x + w / 2.0),
+                    number(line_y),
+                    escape(&line)
+                );
+                line_y += LINE_HEIGHT;
+            }
+            let _ = writeln!(svg, "  </g>");
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+impl Render for Svg {
+    fn render(&self, week_plan: &WeekPlan, out: &mut dyn Write) -> io::Result<()> {
+        out.write_all(self.render_to_string(week_plan).as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::weekplan::Weekday;
+
+    fn block_heights(svg: &str) -> Vec<f64> {
+        svg.lines()
+            .filter(|line| line.contains(r#"rx="4""#))
+            .map(|line| {
+                let height = line.split("height=\"").nth(1).unwrap();
+                height[..height.find('"').unwrap()].parse().unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn proportional_axis() {
+        let mut hourly = WeekPlan::new(Time::new(8, 0).unwrap(), 60, 4).unwrap();
+        hourly
+            .try_insert(Weekday::Monday, Time::new(8, 0).unwrap(), "A".into())
+            .unwrap();
+        let mut long = WeekPlan::new(Time::new(8, 0).unwrap(), 90, 4).unwrap();
+        long.try_insert(Weekday::Monday, Time::new(8, 0).unwrap(), "A".into())
+            .unwrap();
+
+        let hourly = block_heights(&Svg::new().render_to_string(&hourly))[0] + 2.0;
+        let long = block_heights(&Svg::new().render_to_string(&long))[0] + 2.0;
+        assert_eq!(long / hourly, 1.5);
+    }
+
+    #[test]
+    fn merged_blocks_and_gaps() {
+        let mut week_plan = WeekPlan::new(Time::new(8, 30).unwrap(), 90, 4).unwrap();
+        week_plan
+            .try_insert_range(Weekday::Monday, (Time::new(8, 30).unwrap(), 2), "A".into())
+            .unwrap()
+            .try_insert(Weekday::Monday, Time::new(13, 0).unwrap(), "<B>".into())
+            .unwrap();
+
+        let svg = Svg::new().scale(2.0).render_to_string(&week_plan);
+
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert_eq!(block_heights(&svg), [358.0, 178.0]);
+        assert!(svg.contains("<title>Monday 08:30–11:30 A</title>"));
+        assert!(svg.contains("&lt;B&gt;"));
+        // 11:30 to 13:00 is free, so no block covers it.
+        assert_eq!(svg.matches("<g>").count(), 2);
+    }
+}
@@ -0,0 +1,298 @@
+use std::io::{self, Write};
+
+use super::Render;
+use crate::weekplan::locale::Locale;
+use crate::weekplan::{Color, WeekPlan};
+
+/// Columns narrower than this are not shrunk any further, even if the table
+/// ends up wider than the terminal.
+const MIN_COLUMN_WIDTH: usize = 3;
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+
+/// Renders a plan as a table drawn with Unicode box-drawing characters, for
+/// display in a terminal.
+///
+/// Day columns share the available width; activities that don't fit are
+/// wrapped over up to [`Terminal::max_lines`] lines and then truncated with an
+/// ellipsis.
+#[derive(Debug, Clone)]
+pub struct Terminal {
+    locale: Locale,
+    width: usize,
+    colors: bool,
+    max_lines: usize,
+}
+
+impl Default for Terminal {
+    fn default() -> Self {
+        Terminal {
+            locale: Locale::default(),
+            width: 80,
+            colors: false,
+            max_lines: 2,
+        }
+    }
+}
+
+impl Terminal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    /// The width of the terminal in columns, 80 by default.
+    pub fn width(mut self, width: usize) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Whether cells are painted with the color of their activity using ANSI
+    /// escape codes. Disabled by default.
+    pub fn colors(mut self, colors: bool) -> Self {
+        self.colors = colors;
+        self
+    }
+
+    /// The most lines an activity is wrapped over, 2 by default.
+    pub fn max_lines(mut self, max_lines: usize) -> Self {
+        self.max_lines = max_lines.max(1);
+        self
+    }
+
+    pub fn render_to_string(&self, week_plan: &WeekPlan) -> String {
+        let (weekdays, times, table) = week_plan.to_table();
+        let locale = &self.locale;
+
+        let labels: Vec<String> = times
+            .iter()
+            .map(|&time| locale.time(time).to_string())
+            .collect();
+        let label_width = labels
+            .iter()
+            .map(|label| width_of(label))
+            .max()
+            .unwrap_or(0);
+
+        // Every column is padded by a space on both sides and followed by a
+        // border, and the table starts with one.
+        let days = weekdays.len().max(1);
+        let overhead = 1 + (label_width + 3) + days * 3;
+        let natural = weekdays
+            .iter()
+            .map(|&day| width_of(locale.weekday(day)))
+            .chain(table.iter().map(|activity| width_of(activity)))
+            .max()
+            .unwrap_or(0);
+        let column = (self.width.saturating_sub(overhead) / days)
+            .min(natural)
+            .max(MIN_COLUMN_WIDTH);
+
+        let widths: Vec<usize> = std::iter::once(label_width)
+            .chain(weekdays.iter().map(|_| column))
+            .collect();
+
+        let mut out = String::new();
+        out.push_str(&border(&widths, '┌', '┬', '┐'));
+
+        // Fall back to short names for all days as soon as one doesn't fit
+        let short = weekdays
+            .iter()
+            .any(|&day| width_of(locale.weekday(day)) > column);
+        let header: Vec<String> = weekdays
+            .iter()
+            .map(|&day| {
+                let name = if short {
+                    locale.short_weekday(day)
+                } else {
+                    locale.weekday(day)
+                };
+                truncate(name, column)
+            })
+            .collect();
+        out.push('│');
+        out.push_str(&pad("", label_width));
+        for name in &header {
+            if self.colors {
+                out.push_str(&format!("{BOLD}{}{RESET}", pad(name, column)));
+            } else {
+                out.push_str(&pad(name, column));
+            }
+        }
+        out.push('\n');
+
+        for (slot, label) in labels.iter().enumerate() {
+            out.push_str(&border(&widths, '├', '┼', '┤'));
+
+            let cells: Vec<Vec<String>> = (0..weekdays.len())
+                .map(|day| wrap(&table[day * times.len() + slot], column, self.max_lines))
+                .collect();
+            let height = cells.iter().map(Vec::len).max().unwrap_or(0).max(1);
+
+            for line in 0..height {
+                out.push('│');
+                out.push_str(&pad(if line == 0 { label } else { "" }, label_width));
+
+                for (day, cell) in cells.iter().enumerate() {
+                    let text = cell.get(line).map_or("", String::as_str);
+                    let activity = &table[day * times.len() + slot];
+                    if self.colors && !activity.is_empty() {
+                        let background = week_plan.color(activity);
+                        let foreground = background.contrasting_text();
+                        let padded = pad(text, column);
+                        let (padded, border) = padded.split_at(padded.len() - '│'.len_utf8());
+                        out.push_str(&format!(
+                            "{}{}{padded}{RESET}{border}",
+                            ansi(background, 48),
+                            ansi(foreground, 38)
+                        ));
+                    } else {
+                        out.push_str(&pad(text, column));
+                    }
+                }
+                out.push('\n');
+            }
+        }
+
+        out.push_str(&border(&widths, '└', '┴', '┘'));
+        out
+    }
+}
+
+impl Render for Terminal {
+    fn render(&self, week_plan: &WeekPlan, out: &mut dyn Write) -> io::Result<()> {
+        out.write_all(self.render_to_string(week_plan).as_bytes())
+    }
+}
+
+/// The number of terminal columns `text` takes, assuming every character is
+/// one column wide.
+fn width_of(text: &str) -> usize {
+    text.chars().count()
+}
+
+/// A cell padded to `width` with a space on each side, followed by a border.
+fn pad(text: &str, width: usize) -> String {
+    format!(" {text:<width$} │")
+}
+
+fn border(widths: &[usize], left: char, middle: char, right: char) -> String {
+    let columns: Vec<String> = widths.iter().map(|width| "─".repeat(width + 2)).collect();
+    format!("{left}{}{right}\n", columns.join(&middle.to_string()))
+}
+
+/// A 24-bit ANSI color, `layer` being 38 for the text and 48 for the
+/// background.
+fn ansi(color: Color, layer: u8) -> String {
+    format!("\x1b[{layer};2;{};{};{}m", color.r, color.g, color.b)
+}
+
+/// Greedily wraps `text` on whitespace into lines of at most `width`
+/// characters. Words too long for a line of their own are truncated, and so is
+/// text beyond `max_lines` lines.
+fn wrap(text: &str, width: usize, max_lines: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+
+    for word in text.split_whitespace() {
+        if line.is_empty() {
+            line = truncate(word, width);
+        } else if width_of(&line) + 1 + width_of(word) <= width {
+            line.push(' ');
+            line.push_str(word);
+        } else {
+            lines.push(std::mem::replace(&mut line, truncate(word, width)));
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+
+    if lines.len() > max_lines {
+        lines.truncate(max_lines);
+        let last = lines.pop().unwrap_or_default();
+        if !last.ends_with('…') {
+            lines.push(truncate(&format!("{last}…"), width));
+        } else {
+            lines.push(last);
+        }
+    }
+    lines
+}
+
+/// Cuts `text` to `width` characters, ending it with an ellipsis if anything
+/// was removed.
+fn truncate(text: &str, width: usize) -> String {
+    if width_of(text) <= width {
+        return text.to_owned();
+    }
+
+    let kept: String = text.chars().take(width.saturating_sub(1)).collect();
+    format!("{}…", kept.trim_end())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::weekplan::{Time, Weekday};
+
+    #[test]
+    fn table() {
+        let mut week_plan = WeekPlan::new(Time::new(8, 30).unwrap(), 90, 2).unwrap();
+        week_plan
+            .try_insert(Weekday::Monday, Time::new(8, 30).unwrap(), "Math".into())
+            .unwrap()
+            .try_insert(
+                Weekday::Friday,
+                Time::new(10, 0).unwrap(),
+                "Computer architecture".into(),
+            )
+            .unwrap();
+
+        let text = Terminal::new().render_to_string(&week_plan);
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert!(lines.iter().all(|line| width_of(line) <= 80));
+        assert!(lines
+            .iter()
+            .all(|line| width_of(line) == width_of(lines[0])));
+        assert!(lines[0].starts_with("┌───────┬─────────┬"));
+        assert!(lines[1].starts_with("│       │ Mon     │ Tue     │"));
+        assert!(lines[3].starts_with("│ 08:30 │ Math    │         │"));
+        assert!(lines[5].ends_with("│ Comput… │         │         │"));
+        assert!(lines[6].ends_with("│ archit… │         │         │"));
+        assert!(lines[7].starts_with("└───────┴"));
+        assert!(!text.contains('\x1b'));
+
+        let text = Terminal::new().width(200).render_to_string(&week_plan);
+        assert!(text.contains("│ Computer architecture │"));
+        assert!(text.contains("│ Wednesday             │"));
+    }
+
+    #[test]
+    fn colors() {
+        let mut week_plan = WeekPlan::new(Time::new(8, 30).unwrap(), 90, 1).unwrap();
+        week_plan
+            .try_insert(Weekday::Monday, Time::new(8, 30).unwrap(), "Math".into())
+            .unwrap()
+            .set_color("Math", Color::rgb(0, 0, 128));
+
+        let text = Terminal::new().colors(true).render_to_string(&week_plan);
+        assert!(text.contains("│\x1b[48;2;0;0;128m\x1b[38;2;255;255;255m Math "));
+    }
+
+    #[test]
+    fn wrapping() {
+        assert!(wrap("", 5, 2).is_empty());
+        assert_eq!(wrap("Lab of physics", 7, 2), ["Lab of", "physics"]);
+        assert_eq!(wrap("Lab of physics", 7, 1), ["Lab of…"]);
+        assert_eq!(wrap("Electronics", 6, 2), ["Elect…"]);
+        assert_eq!(wrap("Lab of electronics", 6, 2), ["Lab of", "elect…"]);
+        assert_eq!(wrap("Lab of electronics", 6, 1), ["Lab o…"]);
+        assert_eq!(truncate("Monday", 4), "Mon…");
+    }
+}
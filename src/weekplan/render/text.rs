@@ -0,0 +1,101 @@
+//! Text measurement for renderers that lay out text themselves. Widths use
+//! the metrics of Helvetica, which Arial shares.
+
+#[derive(Clone, Copy)]
+pub(crate) enum Font {
+    Regular,
+    Bold,
+}
+
+impl Font {
+    /// Approximate width of `text` in points.
+    pub fn width(&self, text: &str, size: f64) -> f64 {
+        let units: u32 = text.chars().map(helvetica_width).sum();
+        let scale = match self {
+            Font::Regular => 1.0,
+            Font::Bold => 1.08,
+        };
+        f64::from(units) * size * scale / 1000.0
+    }
+}
+
+/// Greedily wraps `text` on whitespace into at most `max_lines` lines.
+pub(crate) fn wrap(text: &str, font: Font, size: f64, width: f64, max_lines: usize) -> Vec<String> {
+    let fits = |line: &str| font.width(line, size) <= width;
+    let mut lines: Vec<String> = Vec::new();
+    let mut words = text.split_whitespace().peekable();
+
+    while let Some(word) = words.next() {
+        let mut line = word.to_owned();
+        while let Some(next) = words.peek() {
+            let candidate = format!("{line} {next}");
+            if !fits(&candidate) {
+                break;
+            }
+            line = candidate;
+            words.next();
+        }
+
+        if lines.len() + 1 == max_lines && words.peek().is_some() {
+            let rest: Vec<&str> = words.by_ref().collect();
+            line = format!("{line} {}", rest.join(" "));
+        }
+        lines.push(truncate(&line, font, size, width));
+
+        if lines.len() == max_lines {
+            break;
+        }
+    }
+
+    lines
+}
+
+pub(crate) fn truncate(line: &str, font: Font, size: f64, width: f64) -> String {
+    if font.width(line, size) <= width {
+        return line.to_owned();
+    }
+
+    let mut truncated: String = line.to_owned();
+    while !truncated.is_empty() && font.width(&format!("{truncated}..."), size) > width {
+        truncated.pop();
+    }
+    format!("{}...", truncated.trim_end())
+}
+
+/// Advance widths of Helvetica in thousandths of the font size.
+fn helvetica_width(c: char) -> u32 {
+    const ASCII: [u16; 95] = [
+        // ' ' to '/'
+        278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278,
+        // '0' to '?'
+        556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556,
+        // '@' to 'O'
+        1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778,
+        // 'P' to '_'
+        667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556,
+        // '`' to 'o'
+        333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556,
+        // 'p' to '~'
+        556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+    ];
+
+    match c {
+        ' '..='~' => u32::from(ASCII[c as usize - 32]),
+        _ => 556,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn wrapping() {
+        let lines = wrap("Computer architectures", Font::Regular, 9.0, 60.0, 3);
+        assert_eq!(lines, ["Computer", "architectures"]);
+
+        let lines = wrap("Computer architectures", Font::Regular, 9.0, 60.0, 1);
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].ends_with("..."));
+    }
+}
@@ -0,0 +1,254 @@
+//! A JSON Schema for plan documents, for editors to validate plan files as
+//! they are written.
+//!
+//! The schema describes the canonical form written by this crate: weekdays in
+//! English and times as `HH:MM`. Reading a plan is more lenient, see
+//! [`Weekday`]'s and [`Time`]'s `FromStr` implementations.
+
+use serde_json::{json, Value};
+
+use super::migration::CURRENT_VERSION;
+use super::{Time, Weekday};
+
+/// A time of day as written by [`Time`]'s `Display`, up to `24:00`.
+const TIME_PATTERN: &str = "([01]?[0-9]|2[0-3]):[0-5][0-9]|24:00";
+
+const COLOR_PATTERN: &str = "^#([0-9a-fA-F]{3}|[0-9a-fA-F]{6})$";
+
+/// The JSON Schema (draft 2020-12) of the current plan version.
+pub fn schema() -> Value {
+    let weekdays: Vec<String> = Weekday::ALL.iter().map(Weekday::to_string).collect();
+
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "Weekly plan",
+        "type": "object",
+        "properties": {
+            "version": {
+                "description": "The version of the plan format. Older plans can be upgraded with `main migrate`.",
+                "const": CURRENT_VERSION
+            },
+            "plan": {
+                "description": "The activity booked in each slot, keyed by `Weekday HH:MM`.",
+                "type": "object",
+                "propertyNames": {
+                    "pattern": format!("^({}) ({TIME_PATTERN})$", weekdays.join("|"))
+                },
+                "additionalProperties": { "type": "string" }
+            },
+            "start": {
+                "description": "The start of the first slot of each day.",
+                "$ref": "#/$defs/time"
+            },
+            "slot_duration": {
+                "description": "The length of a slot in minutes.",
+                "type": "integer",
+                "minimum": 1,
+                "maximum": Time::MINUTES_PER_DAY
+            },
+            "slots": {
+                "description": "The number of slots in a day.",
+                "type": "integer",
+                "minimum": 0,
+                "maximum": u8::MAX
+            },
+            "first_weekday": {
+                "description": "The day columns start from. Monday if missing.",
+                "enum": weekdays
+            },
+            "colors": {
+                "description": "Colors for activities, overriding the ones derived from their names.",
+                "type": "object",
+                "additionalProperties": { "type": "string", "pattern": COLOR_PATTERN }
+            }
+        },
+        "required": ["plan", "start", "slot_duration", "slots"],
+        "additionalProperties": false,
+        "$defs": {
+            "time": {
+                "oneOf": [
+                    {
+                        "type": "string",
+                        "pattern": format!("^({TIME_PATTERN})$")
+                    },
+                    {
+                        "type": "object",
+                        "properties": {
+                            "hour": { "type": "integer", "minimum": 0, "maximum": 24 },
+                            "minute": { "type": "integer", "minimum": 0, "maximum": 59 }
+                        },
+                        "required": ["hour", "minute"],
+                        "additionalProperties": false,
+                        "if": { "properties": { "hour": { "const": 24 } } },
+                        "then": { "properties": { "minute": { "const": 0 } } }
+                    }
+                ]
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use regex::Regex;
+    use serde_json::Map;
+
+    use super::*;
+    use crate::weekplan::{Color, WeekPlan};
+
+    /// Checks `value` against the subset of JSON Schema used by [`schema`].
+    fn validate(root: &Value, schema: &Value, value: &Value) -> bool {
+        let schema = schema.as_object().unwrap();
+        let object = value.as_object();
+
+        schema
+            .iter()
+            .all(|(keyword, expected)| match keyword.as_str() {
+                "$schema" | "title" | "description" | "$defs" | "then" => true,
+                "$ref" => {
+                    let name = expected.as_str().unwrap().strip_prefix("#/$defs/").unwrap();
+                    validate(root, &root["$defs"][name], value)
+                }
+                "type" => match expected.as_str().unwrap() {
+                    "object" => value.is_object(),
+                    "string" => value.is_string(),
+                    "integer" => value.is_i64() || value.is_u64(),
+                    other => panic!("unsupported type {other}"),
+                },
+                "minimum" => value
+                    .as_f64()
+                    .is_none_or(|v| v >= expected.as_f64().unwrap()),
+                "maximum" => value
+                    .as_f64()
+                    .is_none_or(|v| v <= expected.as_f64().unwrap()),
+                "const" => value == expected,
+                "enum" => expected.as_array().unwrap().contains(value),
+                "pattern" => value
+                    .as_str()
+                    .is_none_or(|s| Regex::new(expected.as_str().unwrap()).unwrap().is_match(s)),
+                "oneOf" => {
+                    let matches = expected.as_array().unwrap().iter();
+                    matches.filter(|s| validate(root, s, value)).count() == 1
+                }
+                "if" => !validate(root, expected, value) || validate(root, &schema["then"], value),
+                "required" => object.is_none_or(|object| {
+                    let required = expected.as_array().unwrap();
+                    required
+                        .iter()
+                        .all(|field| object.contains_key(field.as_str().unwrap()))
+                }),
+                "properties" => object.is_none_or(|object| {
+                    object.iter().all(|(key, value)| {
+                        expected
+                            .get(key)
+                            .is_none_or(|property| validate(root, property, value))
+                    })
+                }),
+                "additionalProperties" => object.is_none_or(|object| {
+                    let known = schema.get("properties").and_then(Value::as_object);
+                    object
+                        .iter()
+                        .filter(|(key, _)| !known.is_some_and(|known| known.contains_key(*key)))
+                        .all(|(_, value)| match expected {
+                            Value::Bool(allowed) => *allowed,
+                            additional => validate(root, additional, value),
+                        })
+                }),
+                "propertyNames" => object.is_none_or(|object| {
+                    object
+                        .keys()
+                        .all(|key| validate(root, expected, &Value::from(key.as_str())))
+                }),
+                other => panic!("unsupported keyword {other}"),
+            })
+    }
+
+    /// A plan setting every optional field, as written by `Serialize`.
+    fn document() -> Map<String, Value> {
+        let mut week_plan = WeekPlan::new(Time::new(8, 30).unwrap(), 90, 4).unwrap();
+        week_plan
+            .try_insert(Weekday::Sunday, Time::new(13, 0).unwrap(), "A".into())
+            .unwrap()
+            .set_color("A", Color::rgb(0, 0, 128))
+            .set_first_weekday(Weekday::Tuesday);
+
+        match serde_json::to_value(&week_plan).unwrap() {
+            Value::Object(document) => document,
+            _ => unreachable!(),
+        }
+    }
+
+    fn accepts(document: &Map<String, Value>) -> (bool, bool) {
+        let schema = schema();
+        let document = Value::Object(document.clone());
+        let valid = validate(&schema, &schema, &document);
+        let readable = serde_json::from_value::<WeekPlan>(document).is_ok();
+        (valid, readable)
+    }
+
+    #[test]
+    fn matches_serialization() {
+        let schema = schema();
+        let document = document();
+        let properties = schema["properties"].as_object().unwrap();
+
+        let written: Vec<&String> = document.keys().collect();
+        let described: Vec<&String> = properties.keys().collect();
+        assert_eq!(written, described);
+        assert_eq!(accepts(&document), (true, true));
+    }
+
+    #[test]
+    fn required_fields() {
+        for field in document().keys() {
+            let mut document = document();
+            document.remove(field);
+
+            let (valid, readable) = accepts(&document);
+            assert_eq!(valid, readable, "{field}");
+        }
+    }
+
+    #[test]
+    fn values() {
+        let cases = [
+            ("version", json!(1)),
+            ("version", json!(CURRENT_VERSION)),
+            ("version", json!(CURRENT_VERSION + 1)),
+            ("start", json!("23:59")),
+            ("start", json!("24:01")),
+            ("start", json!({ "hour": 8, "minute": 30 })),
+            ("start", json!({ "hour": 24, "minute": 30 })),
+            ("start", json!({ "hour": 8, "minute": 60 })),
+            ("start", json!({ "hour": 8 })),
+            ("slot_duration", json!(0)),
+            ("slot_duration", json!(-1)),
+            ("slot_duration", json!(Time::MINUTES_PER_DAY)),
+            ("slot_duration", json!(Time::MINUTES_PER_DAY + 1)),
+            ("slots", json!(256)),
+            ("first_weekday", json!("Friday")),
+            ("first_weekday", json!("Fun day")),
+            ("colors", json!({ "A": "#fff" })),
+            ("colors", json!({ "A": "red" })),
+            ("plan", json!({ "Monday 8:30": "A" })),
+            ("plan", json!({ "Monday 08:30": 1 })),
+            ("plan", json!({ "Moonday 08:30": "A" })),
+            ("plan", json!({ "Monday 08:61": "A" })),
+            ("unknown", json!(1)),
+        ];
+
+        for (field, value) in cases {
+            let mut document = document();
+            document.insert(field.to_owned(), value.clone());
+            // Large starts and durations would push the slots past midnight,
+            // so keep the plan empty and short when changing them.
+            if field == "start" || field == "slot_duration" {
+                document.insert("plan".to_owned(), json!({}));
+                document.insert("slots".to_owned(), json!(0));
+            }
+
+            let (valid, readable) = accepts(&document);
+            assert_eq!(valid, readable, "{field}: {value}");
+        }
+    }
+}
@@ -1,11 +1,13 @@
 use std::collections::HashMap;
-use std::fmt;
 
-use serde::de::{self, Deserializer, MapAccess, Visitor};
+use serde::de::{self, Deserializer};
 use serde::ser::{Serialize, SerializeStruct, Serializer};
 use serde::Deserialize;
+use serde_json::Value;
 
-use crate::weekplan::{Slot, Time};
+use crate::weekplan::migration::CURRENT_VERSION;
+use crate::weekplan::validation;
+use crate::weekplan::{Activity, Slot, Time, Weekday};
 
 use super::WeekPlan;
 
@@ -13,6 +15,7 @@ mod time_impl {
     use super::Time;
     use serde::de::{self, Deserialize, Deserializer, MapAccess, Visitor};
     use std::fmt;
+    use std::str::FromStr;
 
     enum Field {
         Hour,
@@ -26,7 +29,14 @@ mod time_impl {
         type Value = Time;
 
         fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-            formatter.write_str("struct Time")
+            formatter.write_str("a time string like \"08:30\" or struct Time")
+        }
+
+        fn visit_str<E>(self, value: &str) -> Result<Time, E>
+        where
+            E: de::Error,
+        {
+            Time::from_str(value).map_err(de::Error::custom)
         }
 
         fn visit_map<V>(self, mut map: V) -> Result<Time, V::Error>
@@ -96,8 +106,7 @@ impl<'de> Deserialize<'de> for Time {
     where
         D: Deserializer<'de>,
     {
-        const FIELDS: &'static [&'static str] = &["hour", "minute"];
-        deserializer.deserialize_struct("Time", FIELDS, time_impl::TimeVisitor)
+        deserializer.deserialize_any(time_impl::TimeVisitor)
     }
 }
 
@@ -105,112 +114,152 @@ impl Serialize for Time {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+/// Serializes a [`Time`] as a `{"hour": 8, "minute": 30}` object instead of the
+/// default `"08:30"` string. Meant for `#[serde(with = "time_object")]`;
+/// deserialization accepts both forms.
+pub mod time_object {
+    use serde::ser::SerializeStruct;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use crate::weekplan::Time;
+
+    pub fn serialize<S>(time: &Time, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
     {
         let mut s = serializer.serialize_struct("Time", 2)?;
-        s.serialize_field("hour", &self.hour())?;
-        s.serialize_field("minute", &self.minute())?;
+        s.serialize_field("hour", &time.hour())?;
+        s.serialize_field("minute", &time.minute())?;
         s.end()
     }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Time, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Time::deserialize(deserializer)
+    }
 }
 
-impl Serialize for WeekPlan {
+/// Writes the bookings by weekday and time, so that plan files are stable and
+/// easy to edit by hand.
+struct SortedPlan<'a>(&'a HashMap<Slot, Activity>);
+
+impl Serialize for SortedPlan<'_> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        let mut s = serializer.serialize_struct("WeekPlan", 4)?;
-        s.serialize_field("plan", &self.plan)?;
-        s.serialize_field("start", &self.start)?;
+        let mut bookings: Vec<_> = self.0.iter().collect();
+        bookings.sort_by_key(|(Slot(weekday, time), _)| (*weekday, *time));
+        serializer.collect_map(bookings)
+    }
+}
+
+/// Writes a [`Time`] through [`time_object`].
+struct TimeObject(Time);
+
+impl Serialize for TimeObject {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        time_object::serialize(&self.0, serializer)
+    }
+}
+
+/// A plan serialized with its `start` as a `{"hour": 8, "minute": 30}` object,
+/// see [`WeekPlan::with_time_objects`].
+#[derive(Debug, Clone, Copy)]
+pub struct TimeObjects<'a>(&'a WeekPlan);
+
+impl WeekPlan {
+    /// Serializes the plan with `start` written as a `{"hour": 8, "minute":
+    /// 30}` object instead of an `"08:30"` string. Both forms read back the
+    /// same.
+    pub fn with_time_objects(&self) -> TimeObjects<'_> {
+        TimeObjects(self)
+    }
+
+    fn serialize_with<S>(&self, time_objects: bool, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut s = serializer.serialize_struct("WeekPlan", 7)?;
+        s.serialize_field("version", &CURRENT_VERSION)?;
+        s.serialize_field("plan", &SortedPlan(&self.plan))?;
+        if time_objects {
+            s.serialize_field("start", &TimeObject(self.start))?;
+        } else {
+            s.serialize_field("start", &self.start)?;
+        }
         s.serialize_field("slot_duration", &self.slot_duration)?;
         s.serialize_field("slots", &self.slots)?;
+        if self.first_weekday == Weekday::Monday {
+            s.skip_field("first_weekday")?;
+        } else {
+            s.serialize_field("first_weekday", &self.first_weekday)?;
+        }
+        if self.colors.is_empty() {
+            s.skip_field("colors")?;
+        } else {
+            s.serialize_field("colors", &self.colors)?;
+        }
         s.end()
     }
 }
 
+impl Serialize for WeekPlan {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.serialize_with(false, serializer)
+    }
+}
+
+impl Serialize for TimeObjects<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0.serialize_with(true, serializer)
+    }
+}
+
+/// Plans are read by `validation::read`, so that deserializing and
+/// validating a document accept the same plans.
 impl<'de> Deserialize<'de> for WeekPlan {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: Deserializer<'de>,
     {
-        #[derive(Deserialize)]
-        #[serde(field_identifier, rename_all = "snake_case")]
-        enum Field {
-            Plan,
-            Start,
-            SlotDuration,
-            Slots,
+        let document = Value::deserialize(deserializer)?;
+
+        // Unversioned documents share the current layout closely enough to be
+        // read directly; anything else must go through `migration::migrate`
+        // first.
+        if let Some(version) = document.get("version").filter(|&v| *v != CURRENT_VERSION) {
+            return Err(de::Error::custom(format!(
+                "unsupported plan version {version}, expected {CURRENT_VERSION}"
+            )));
         }
 
-        struct WeekPlanVisitor;
-
-        impl<'de> Visitor<'de> for WeekPlanVisitor {
-            type Value = WeekPlan;
-
-            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                formatter.write_str("struct WeekPlan")
-            }
-
-            fn visit_map<V>(self, mut map: V) -> Result<WeekPlan, V::Error>
-            where
-                V: MapAccess<'de>,
-            {
-                let mut plan: Option<HashMap<Slot, String>> = None;
-                let mut start: Option<Time> = None;
-                let mut slot_duration: Option<u16> = None;
-                let mut slots: Option<u8> = None;
-
-                while let Some(key) = map.next_key()? {
-                    match key {
-                        Field::Plan => {
-                            if plan.is_some() {
-                                return Err(de::Error::duplicate_field("plan"));
-                            }
-                            plan = Some(map.next_value()?);
-                        }
-                        Field::Start => {
-                            if start.is_some() {
-                                return Err(de::Error::duplicate_field("start"));
-                            }
-                            start = Some(map.next_value()?);
-                        }
-                        Field::SlotDuration => {
-                            if slot_duration.is_some() {
-                                return Err(de::Error::duplicate_field("slot_duration"));
-                            }
-                            slot_duration = Some(map.next_value()?);
-                        }
-                        Field::Slots => {
-                            if slots.is_some() {
-                                return Err(de::Error::duplicate_field("slots"));
-                            }
-                            slots = Some(map.next_value()?);
-                        }
-                    }
-                }
-
-                let plan = plan.ok_or_else(|| de::Error::missing_field("plan"))?;
-                let start = start.ok_or_else(|| de::Error::missing_field("start"))?;
-                let slot_duration =
-                    slot_duration.ok_or_else(|| de::Error::missing_field("slot_duration"))?;
-                let slots = slots.ok_or_else(|| de::Error::missing_field("slots"))?;
-
-                let mut week_plan = WeekPlan::new(start, slot_duration, slots)
-                    .ok_or_else(|| de::Error::custom("Invalid WeekPlan"))?;
-
-                for (Slot(weekday, slot), activity) in plan {
-                    week_plan
-                        .try_insert(weekday, slot, activity)
-                        .map_err(|err| {
-                            de::Error::custom(format!("Invalid slot {slot} for activity: {err:?}"))
-                        })?;
-                }
-
-                Ok(week_plan)
+        validation::read(document).map_err(|report| {
+            let problem = &report.problems()[0];
+            match &problem.value {
+                Some(value) => de::Error::custom(format!(
+                    "{}: {} (found {value})",
+                    problem.path, problem.message
+                )),
+                None => de::Error::custom(format!("{}: {}", problem.path, problem.message)),
             }
-        }
-
-        const FIELDS: &'static [&'static str] = &["plan", "start", "slot_duration", "slots"];
-        deserializer.deserialize_struct("WeekPlan", FIELDS, WeekPlanVisitor)
+        })
     }
 }
 
@@ -224,7 +273,7 @@ mod test {
         let json_out = serde_json::to_string(&day_in).unwrap();
 
         let json_in = r#""Monday""#;
-        let day_out: Weekday = serde_json::from_str(&json_in).unwrap();
+        let day_out: Weekday = serde_json::from_str(json_in).unwrap();
 
         assert_eq!(day_in, day_out);
         assert_eq!(json_in, json_out);
@@ -235,11 +284,29 @@ mod test {
         let time_in = Time::new(8, 30).unwrap();
         let json_out = serde_json::to_string(&time_in).unwrap();
 
+        let json_in = r#""08:30""#;
+        let time_out: Time = serde_json::from_str(json_in).unwrap();
+
+        assert_eq!(time_in, time_out);
+        assert_eq!(json_in, json_out);
+    }
+
+    #[test]
+    fn time_object() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Wrapper(#[serde(with = "super::time_object")] Time);
+
+        let time_in = Time::new(8, 30).unwrap();
+        let json_out = serde_json::to_string(&Wrapper(time_in)).unwrap();
+
         let json_in = r#"{"hour":8,"minute":30}"#;
-        let time_out: Time = serde_json::from_str(&r#"{"hour": 8, "minute": 30}"#).unwrap();
+        let time_out: Time = serde_json::from_str(r#"{"hour": 8, "minute": 30}"#).unwrap();
 
         assert_eq!(time_in, time_out);
         assert_eq!(json_in, json_out);
+
+        let err = serde_json::from_str::<Time>(r#""8:75""#).unwrap_err();
+        assert!(err.to_string().contains("invalid minute"));
     }
 
     #[test]
@@ -251,10 +318,41 @@ mod test {
             .try_insert(Weekday::Tuesday, Time::new(10, 00).unwrap(), "BBB".into())
             .unwrap();
 
+        week_plan_in
+            .set_first_weekday(Weekday::Sunday)
+            .set_color("AAA", Color::rgb(0, 152, 121));
+
         let week_plan_in = week_plan_in;
         let json = serde_json::to_string(&week_plan_in).unwrap();
         let week_plan_out: WeekPlan = serde_json::from_str(&json).unwrap();
 
         assert_eq!(week_plan_in, week_plan_out);
     }
+
+    #[test]
+    fn weekplan_time_objects() {
+        let week_plan_in = WeekPlan::new(Time::new(8, 30).unwrap(), 90, 7).unwrap();
+
+        let json = serde_json::to_value(week_plan_in.with_time_objects()).unwrap();
+        assert_eq!(json["start"], serde_json::json!({"hour": 8, "minute": 30}));
+
+        let week_plan_out: WeekPlan = serde_json::from_value(json).unwrap();
+        assert_eq!(week_plan_in, week_plan_out);
+    }
+
+    #[test]
+    fn weekplan_errors() {
+        let err = serde_json::from_str::<WeekPlan>(
+            r#"{"plan": {"Moonday 08:30": "A"}, "start": "08:30", "slot_duration": 90, "slots": 7}"#,
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with(r#"$.plan["Moonday 08:30"]: invalid weekday (found "Moonday")"#));
+
+        let err = serde_json::from_str::<WeekPlan>(r#"{"version": 1, "plan": {}}"#).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("unsupported plan version 1, expected 2"));
+    }
 }
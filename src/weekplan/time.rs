@@ -2,35 +2,17 @@ use std::fmt;
 use std::str::FromStr;
 use std::{cmp::Ordering, ops::Deref};
 
+pub use format::FormattedTime;
+pub use weekly_planner_core::time::{ParseTimeError, ParseTimeErrorKind};
+
+mod format;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Time {
     hour: Hour,
     minute: Minute,
 }
 
-#[derive(Debug)]
-pub enum ParseTimeError {
-    BadFormat,
-    InvalidHour,
-    InvalidMinute,
-    BreaksInvariant,
-}
-
-impl fmt::Display for ParseTimeError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let msg = match self {
-            ParseTimeError::BadFormat => "bad format",
-            ParseTimeError::InvalidHour => "invalid hour",
-            ParseTimeError::InvalidMinute => "invalid minute",
-            ParseTimeError::BreaksInvariant => "time doesn't respect the invariants for Time",
-        };
-
-        write!(f, "{msg}")
-    }
-}
-
-impl std::error::Error for ParseTimeError {}
-
 impl fmt::Display for Time {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{:02}:{:02}", self.hour(), self.minute())
@@ -38,13 +20,54 @@ impl fmt::Display for Time {
 }
 
 impl Time {
+    /// The start of the day, `00:00`.
+    pub const MIDNIGHT: Time = Time {
+        hour: Hour(0),
+        minute: Minute(0),
+    };
+
+    /// The end of the day, `24:00`. It is only meaningful as the exclusive
+    /// end of a range: no slot can start at this time.
+    pub const END_OF_DAY: Time = Time {
+        hour: Hour(24),
+        minute: Minute(0),
+    };
+
+    /// `08:30`, the format used by `Display`.
+    pub const PATTERN_24H: &'static str = "HH:mm";
+
+    /// `8:30 AM`.
+    pub const PATTERN_12H: &'static str = "h:mm a";
+
+    /// Minutes in a day, i.e. `END_OF_DAY.to_minutes()`.
+    pub const MINUTES_PER_DAY: u16 = weekly_planner_core::time::MINUTES_PER_DAY;
+
+    /// Builds a time from its hour and minute. `24:00` is accepted and yields
+    /// [`Time::END_OF_DAY`]; any other time past midnight is rejected.
     pub fn new(hour: u8, minute: u8) -> Option<Self> {
         let hour = Hour::new(hour)?;
         let minute = Minute::new(minute)?;
 
+        if *hour == 24 && *minute != 0 {
+            return None;
+        }
+
         Some(Self { hour, minute })
     }
 
+    /// Builds a time from the number of minutes elapsed since midnight.
+    /// `MINUTES_PER_DAY` yields [`Time::END_OF_DAY`].
+    pub fn from_minutes(minutes: u16) -> Option<Self> {
+        if minutes > Self::MINUTES_PER_DAY {
+            return None;
+        }
+
+        let hour = u8::try_from(minutes / 60).ok()?;
+        let minute = u8::try_from(minutes % 60).ok()?;
+
+        Time::new(hour, minute)
+    }
+
     fn unpack(&self) -> (u8, u8) {
         (*self.hour, *self.minute)
     }
@@ -54,15 +77,40 @@ impl Time {
         u16::from(minute) + u16::from(hour) * 60
     }
 
+    /// Moves the time forward by `duration` minutes. Returns `None` if the
+    /// result would go past [`Time::END_OF_DAY`].
     pub fn try_sum(&self, duration: u16) -> Option<Self> {
-        let (hour, minute) = self.unpack();
+        Time::from_minutes(self.to_minutes().checked_add(duration)?)
+    }
 
-        let minute = u16::from(minute) + duration;
+    /// Moves the time backward by `duration` minutes. Returns `None` if the
+    /// result would go before [`Time::MIDNIGHT`].
+    pub fn checked_sub(&self, duration: u16) -> Option<Self> {
+        Time::from_minutes(self.to_minutes().checked_sub(duration)?)
+    }
 
-        let hour = hour + u8::try_from(minute / 60).ok()?;
-        let minute = u8::try_from(minute % 60).unwrap();
+    /// Minutes from `self` to `other`. Returns `None` if `other` comes
+    /// before `self`.
+    pub fn minutes_until(&self, other: Time) -> Option<u16> {
+        other.to_minutes().checked_sub(self.to_minutes())
+    }
 
-        Time::new(hour, minute)
+    /// Formats the time through a pattern made of these fields:
+    ///
+    /// - `HH`/`H`: hour of the day (0-24), zero-padded or not
+    /// - `hh`/`h`: hour on a 12-hour clock (1-12), zero-padded or not
+    /// - `mm`/`m`: minute, zero-padded or not
+    /// - `a`: `AM` or `PM`
+    /// - `'text'`: literal text, `''` for a single quote
+    ///
+    /// Any other character is copied as is. See [`Time::PATTERN_24H`] and
+    /// [`Time::PATTERN_12H`] for the common cases.
+    pub fn format<'a>(&self, pattern: &'a str) -> FormattedTime<'a> {
+        FormattedTime::new(*self, pattern)
+    }
+
+    pub fn is_end_of_day(&self) -> bool {
+        *self == Time::END_OF_DAY
     }
 
     pub fn hour(&self) -> u8 {
@@ -74,31 +122,25 @@ impl Time {
     }
 }
 
+impl Ord for Time {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.to_minutes().cmp(&other.to_minutes())
+    }
+}
+
 impl PartialOrd for Time {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        match self.hour.partial_cmp(&other.hour) {
-            Some(Ordering::Equal) => self.minute.partial_cmp(&other.minute),
-            ordering => ordering,
-        }
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
 }
 
+/// Accepts `8:30`, `08:30`, `8.30`, `8h30`, `0830`, `8:30am`, `8:30 PM` and
+/// ISO-8601 times like `T08:30:00`.
 impl FromStr for Time {
     type Err = ParseTimeError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parts: Vec<&str> = s.split(":").map(|s| s.trim()).collect();
-
-        if parts.len() != 2 {
-            return Err(ParseTimeError::BadFormat);
-        }
-
-        let hour = unsafe { *parts.get_unchecked(0) };
-        let minute = unsafe { *parts.get_unchecked(1) };
-
-        let hour = u8::from_str(hour).map_err(|_| ParseTimeError::InvalidHour)?;
-        let minute = u8::from_str(minute).map_err(|_| ParseTimeError::InvalidMinute)?;
-
-        Time::new(hour, minute).ok_or(ParseTimeError::BreaksInvariant)
+        let (hour, minute) = weekly_planner_core::time::parse(s)?;
+        Ok(Time::new(hour, minute).expect("parsed times are valid"))
     }
 }
 
@@ -110,7 +152,7 @@ struct Minute(u8);
 
 impl Hour {
     fn new(hour: u8) -> Option<Self> {
-        if hour < 24 {
+        if hour <= 24 {
             Some(Self(hour))
         } else {
             None
@@ -143,3 +185,122 @@ impl Deref for Minute {
         &self.0
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn end_of_day() {
+        assert_eq!(Time::new(24, 0), Some(Time::END_OF_DAY));
+        assert_eq!(Time::new(24, 30), None);
+        assert_eq!(
+            Time::from_minutes(Time::MINUTES_PER_DAY),
+            Some(Time::END_OF_DAY)
+        );
+        assert_eq!(Time::from_minutes(Time::MINUTES_PER_DAY + 1), None);
+        assert_eq!(
+            Time::new(22, 30).unwrap().try_sum(90),
+            Some(Time::END_OF_DAY)
+        );
+        assert_eq!(Time::END_OF_DAY.try_sum(1), None);
+    }
+
+    #[test]
+    fn arithmetic() {
+        let time = Time::new(10, 15).unwrap();
+
+        assert_eq!(time.try_sum(50), Time::new(11, 5));
+        assert_eq!(time.checked_sub(30), Time::new(9, 45));
+        assert_eq!(time.checked_sub(616), None);
+        assert_eq!(Time::from_minutes(615), Some(time));
+        assert_eq!(Time::MIDNIGHT.minutes_until(time), Some(615));
+        assert_eq!(time.minutes_until(Time::MIDNIGHT), None);
+    }
+
+    #[test]
+    fn parse() {
+        let expected = Time::new(8, 30).unwrap();
+        for input in [
+            "8:30",
+            "08:30",
+            " 8:30 ",
+            "8.30",
+            "8h30",
+            "0830",
+            "830",
+            "8:30am",
+            "8:30 AM",
+            "8:30 a.m.",
+            "T08:30",
+            "T08:30:00",
+            "08:30:00",
+        ] {
+            assert_eq!(input.parse(), Ok(expected), "{input}");
+        }
+
+        assert_eq!("8h".parse(), Ok(Time::new(8, 0).unwrap()));
+        assert_eq!("12am".parse(), Ok(Time::MIDNIGHT));
+        assert_eq!("12:15 pm".parse(), Ok(Time::new(12, 15).unwrap()));
+        assert_eq!("2:45PM".parse(), Ok(Time::new(14, 45).unwrap()));
+        assert_eq!("24:00".parse(), Ok(Time::END_OF_DAY));
+    }
+
+    #[test]
+    fn parse_error() {
+        let cases = [
+            ("", ParseTimeErrorKind::Empty, 0),
+            ("8", ParseTimeErrorKind::BadFormat, 1),
+            ("8:30x", ParseTimeErrorKind::BadFormat, 4),
+            ("ab:30", ParseTimeErrorKind::BadFormat, 0),
+            ("25:00", ParseTimeErrorKind::InvalidHour, 0),
+            ("13pm", ParseTimeErrorKind::InvalidHour, 0),
+            ("8:7", ParseTimeErrorKind::InvalidMinute, 2),
+            (" 8:75", ParseTimeErrorKind::InvalidMinute, 3),
+            ("T08:30:15", ParseTimeErrorKind::InvalidSecond, 7),
+            ("24:30", ParseTimeErrorKind::BreaksInvariant, 0),
+        ];
+
+        for (input, kind, position) in cases {
+            let err = input.parse::<Time>().unwrap_err();
+            assert_eq!(err.kind(), kind, "{input}");
+            assert_eq!(err.position(), position, "{input}");
+            assert_eq!(err.input(), input);
+        }
+    }
+
+    #[test]
+    fn format() {
+        let morning = Time::new(8, 5).unwrap();
+        let evening = Time::new(20, 30).unwrap();
+
+        assert_eq!(morning.format(Time::PATTERN_24H).to_string(), "08:05");
+        assert_eq!(morning.format(Time::PATTERN_12H).to_string(), "8:05 AM");
+        assert_eq!(evening.format(Time::PATTERN_12H).to_string(), "8:30 PM");
+        assert_eq!(Time::MIDNIGHT.format("hh:mm a").to_string(), "12:00 AM");
+        assert_eq!(evening.format("H.mm").to_string(), "20.30");
+        assert_eq!(evening.format("H'h'mm").to_string(), "20h30");
+        assert_eq!(evening.format("'o''clock' H").to_string(), "o'clock 20");
+    }
+
+    #[test]
+    fn ordering() {
+        let mut times = vec![
+            Time::END_OF_DAY,
+            Time::new(9, 45).unwrap(),
+            Time::MIDNIGHT,
+            Time::new(9, 30).unwrap(),
+        ];
+        times.sort();
+
+        assert_eq!(
+            times,
+            vec![
+                Time::MIDNIGHT,
+                Time::new(9, 30).unwrap(),
+                Time::new(9, 45).unwrap(),
+                Time::END_OF_DAY,
+            ]
+        );
+    }
+}
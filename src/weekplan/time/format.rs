@@ -0,0 +1,59 @@
+use std::fmt;
+
+use super::Time;
+
+/// A [`Time`] rendered through a pattern, see [`Time::format`].
+pub struct FormattedTime<'a> {
+    time: Time,
+    pattern: &'a str,
+}
+
+impl<'a> FormattedTime<'a> {
+    pub(super) fn new(time: Time, pattern: &'a str) -> Self {
+        Self { time, pattern }
+    }
+}
+
+impl fmt::Display for FormattedTime<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hour = self.time.hour();
+        let minute = self.time.minute();
+        let hour12 = match hour % 12 {
+            0 => 12,
+            h => h,
+        };
+        let meridiem = if (12..24).contains(&hour) { "PM" } else { "AM" };
+
+        let mut chars = self.pattern.chars().peekable();
+        while let Some(c) = chars.next() {
+            let mut count = 1;
+            while chars.peek() == Some(&c) && "Hhma'".contains(c) {
+                chars.next();
+                count += 1;
+            }
+
+            match (c, count) {
+                ('H', 1) => write!(f, "{hour}")?,
+                ('H', _) => write!(f, "{hour:02}")?,
+                ('h', 1) => write!(f, "{hour12}")?,
+                ('h', _) => write!(f, "{hour12:02}")?,
+                ('m', 1) => write!(f, "{minute}")?,
+                ('m', _) => write!(f, "{minute:02}")?,
+                ('a', _) => f.write_str(meridiem)?,
+                ('\'', 1) => {
+                    while let Some(literal) = chars.next() {
+                        if literal == '\'' && chars.next_if_eq(&'\'').is_none() {
+                            break;
+                        }
+                        write!(f, "{literal}")?;
+                    }
+                }
+                // Two consecutive quotes are an escaped quote.
+                ('\'', _) => f.write_str("'")?,
+                (c, _) => write!(f, "{c}")?,
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,740 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use serde_json::{Map, Value};
+
+use super::locale::Language;
+use super::migration;
+use super::{Color, Error, Time, WeekPlan, Weekday};
+
+/// A 1-based position in the source document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// A single issue found in a plan document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    /// Where the issue is, as a JSON path like `$.plan["Monday 14:00"]`.
+    pub path: String,
+    pub location: Location,
+    /// The offending value, as it appears in the document.
+    pub value: Option<String>,
+    pub message: String,
+    pub suggestion: Option<String>,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Location { line, column } = self.location;
+        write!(f, "{line}:{column}: {}: {}", self.path, self.message)?;
+        if let Some(value) = &self.value {
+            write!(f, " (found {value})")?;
+        }
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, "\n    help: {suggestion}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Every problem found in a plan document, sorted by location.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    problems: Vec<Problem>,
+}
+
+impl Report {
+    pub fn problems(&self) -> &[Problem] {
+        &self.problems
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, problem) in self.problems.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{problem}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for Report {}
+
+/// Parses and validates a JSON plan document of any supported version.
+///
+/// Unlike deserializing straight into a [`WeekPlan`], which stops at the
+/// first error, this reports every problem in the document along with its
+/// location and, where possible, a way to fix it.
+pub fn validate(source: &str) -> Result<WeekPlan, Report> {
+    let document: Value = serde_json::from_str(source).map_err(|err| Report {
+        problems: vec![Problem {
+            path: "$".to_owned(),
+            location: Location {
+                line: err.line(),
+                column: err.column(),
+            },
+            value: None,
+            message: err.to_string(),
+            suggestion: None,
+        }],
+    })?;
+
+    let mut validator = Validator {
+        locator: Locator::new(source),
+        problems: Vec::new(),
+    };
+    let week_plan = validator.document(document);
+    validator.finish(week_plan)
+}
+
+/// Reads a plan document of the current version, as deserializing a
+/// [`WeekPlan`] does. Without a source to point into, every problem is
+/// located at the start of the document.
+pub(crate) fn read(document: Value) -> Result<WeekPlan, Report> {
+    let mut validator = Validator {
+        locator: Locator::default(),
+        problems: Vec::new(),
+    };
+    let week_plan = validator.fields(document);
+    validator.finish(week_plan)
+}
+
+struct Validator {
+    locator: Locator,
+    problems: Vec<Problem>,
+}
+
+impl Validator {
+    fn finish(self, week_plan: Option<WeekPlan>) -> Result<WeekPlan, Report> {
+        match week_plan {
+            Some(week_plan) if self.problems.is_empty() => Ok(week_plan),
+            _ => {
+                let mut problems = self.problems;
+                problems.sort_by_key(|problem| problem.location);
+                Err(Report { problems })
+            }
+        }
+    }
+
+    fn report(
+        &mut self,
+        path: &str,
+        location: Location,
+        value: Option<&Value>,
+        message: impl Into<String>,
+        suggestion: Option<String>,
+    ) {
+        self.problems.push(Problem {
+            path: path.to_owned(),
+            location,
+            value: value.map(Value::to_string),
+            message: message.into(),
+            suggestion,
+        });
+    }
+
+    fn report_value(&mut self, path: &str, value: &Value, message: impl Into<String>) {
+        let location = self.locator.value(path);
+        self.report(path, location, Some(value), message, None);
+    }
+
+    fn document(&mut self, document: Value) -> Option<WeekPlan> {
+        let document = match migration::migrate(document) {
+            Ok(document) => document,
+            Err(err) => {
+                let path = if self.locator.values.contains_key("$.version") {
+                    "$.version"
+                } else {
+                    "$"
+                };
+                let location = self.locator.value(path);
+                self.report(path, location, None, err.to_string(), None);
+                return None;
+            }
+        };
+
+        self.fields(document)
+    }
+
+    /// Reads the fields of a document of the current version.
+    fn fields(&mut self, document: Value) -> Option<WeekPlan> {
+        let Value::Object(mut fields) = document else {
+            self.report_value("$", &document, "expected a plan object");
+            return None;
+        };
+
+        fields.remove("version");
+        let first_weekday = fields.remove("first_weekday").and_then(|value| {
+            serde_json::from_value::<Weekday>(value.clone())
+                .map_err(|err| self.report_value("$.first_weekday", &value, err.to_string()))
+                .ok()
+        });
+        let colors = self.colors(fields.remove("colors"));
+        let start = self.field(&mut fields, "start", |value| {
+            serde_json::from_value::<Time>(value.clone()).map_err(|err| err.to_string())
+        });
+        let slot_duration = self.field(&mut fields, "slot_duration", |value| {
+            value
+                .as_u64()
+                .and_then(|n| u16::try_from(n).ok())
+                .filter(|n| (1..=Time::MINUTES_PER_DAY).contains(n))
+                .ok_or_else(|| {
+                    format!(
+                        "expected a number of minutes between 1 and {}",
+                        Time::MINUTES_PER_DAY
+                    )
+                })
+        });
+        let slots = self.field(&mut fields, "slots", |value| {
+            value
+                .as_u64()
+                .and_then(|n| u8::try_from(n).ok())
+                .ok_or_else(|| "expected a number of slots between 0 and 255".to_owned())
+        });
+        let plan = self.field(&mut fields, "plan", |value| {
+            value
+                .as_object()
+                .cloned()
+                .ok_or_else(|| "expected an object of \"Weekday HH:MM\" keys".to_owned())
+        });
+
+        for (key, value) in fields {
+            let path = child("$", &key);
+            let location = self.locator.key(&path);
+            self.report(
+                &path,
+                location,
+                Some(&value),
+                format!("unknown field `{key}`"),
+                Some("remove this field".to_owned()),
+            );
+        }
+
+        let week_plan = match (start, slot_duration, slots) {
+            (Some(start), Some(slot_duration), Some(slots)) => {
+                let week_plan = WeekPlan::new(start, slot_duration, slots);
+                if week_plan.is_none() {
+                    let max_slots = start
+                        .minutes_until(Time::END_OF_DAY)
+                        .map(|minutes| minutes / slot_duration)
+                        .unwrap_or_default();
+                    let location = self.locator.value("$.slots");
+                    self.report(
+                        "$.slots",
+                        location,
+                        Some(&Value::from(slots)),
+                        format!(
+                            "{slots} slots of {slot_duration} minutes from {start} end after midnight"
+                        ),
+                        Some(format!("use at most {max_slots} slots")),
+                    );
+                }
+                week_plan
+            }
+            _ => None,
+        };
+
+        match (week_plan, plan) {
+            (Some(mut week_plan), Some(plan)) => {
+                week_plan.set_first_weekday(first_weekday.unwrap_or(Weekday::Monday));
+                for (activity, color) in colors {
+                    week_plan.set_color(activity, color);
+                }
+                self.plan(Some(&mut week_plan), plan);
+                Some(week_plan)
+            }
+            (None, Some(plan)) => {
+                self.plan(None, plan);
+                None
+            }
+            (_, None) => None,
+        }
+    }
+
+    fn field<T>(
+        &mut self,
+        fields: &mut Map<String, Value>,
+        name: &str,
+        parse: impl FnOnce(&Value) -> Result<T, String>,
+    ) -> Option<T> {
+        let path = child("$", name);
+        let Some(value) = fields.remove(name) else {
+            let location = self.locator.value("$");
+            self.report("$", location, None, format!("missing field `{name}`"), None);
+            return None;
+        };
+
+        parse(&value)
+            .map_err(|message| self.report_value(&path, &value, message))
+            .ok()
+    }
+
+    fn colors(&mut self, colors: Option<Value>) -> Vec<(String, Color)> {
+        let Some(colors) = colors else {
+            return Vec::new();
+        };
+        let Value::Object(colors) = colors else {
+            self.report_value("$.colors", &colors, "expected an object of activity colors");
+            return Vec::new();
+        };
+
+        colors
+            .into_iter()
+            .filter_map(|(activity, value)| {
+                let path = child("$.colors", &activity);
+                match value.as_str().map(Color::from_str) {
+                    Some(Ok(color)) => Some((activity, color)),
+                    Some(Err(err)) => {
+                        self.report_value(&path, &value, err.to_string());
+                        None
+                    }
+                    None => {
+                        self.report_value(&path, &value, "expected a \"#rrggbb\" color");
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Checks every booking. Without a `week_plan`, only the keys and values
+    /// themselves are checked.
+    fn plan(&mut self, mut week_plan: Option<&mut WeekPlan>, plan: Map<String, Value>) {
+        for (key, activity) in plan {
+            let path = child("$.plan", &key);
+            let key_location = self.locator.key(&path);
+            let key_value = Value::from(key.as_str());
+
+            let Some(activity) = activity.as_str().map(str::to_owned) else {
+                self.report_value(&path, &activity, "expected an activity name");
+                continue;
+            };
+
+            let Some((weekday, time)) = key.trim().split_once(char::is_whitespace) else {
+                self.report(
+                    &path,
+                    key_location,
+                    Some(&key_value),
+                    "expected a \"Weekday HH:MM\" key",
+                    None,
+                );
+                continue;
+            };
+            let time_offset = offset_in(&key, time);
+
+            let weekday = match Weekday::from_str(weekday) {
+                Ok(weekday) => weekday,
+                Err(_) => {
+                    let suggestion = closest_weekday(weekday)
+                        .map(|closest| format!("did you mean `{closest}`?"));
+                    self.report(
+                        &path,
+                        shift(key_location, &key, offset_in(&key, weekday)),
+                        Some(&Value::from(weekday)),
+                        "invalid weekday",
+                        suggestion,
+                    );
+                    continue;
+                }
+            };
+
+            let time = match Time::from_str(time) {
+                Ok(time) => time,
+                Err(err) => {
+                    self.report(
+                        &path,
+                        shift(key_location, &key, time_offset + err.position()),
+                        Some(&Value::from(time)),
+                        err.kind().to_string(),
+                        None,
+                    );
+                    continue;
+                }
+            };
+
+            let Some(week_plan) = week_plan.as_deref_mut() else {
+                continue;
+            };
+
+            match week_plan.try_insert(weekday, time, activity) {
+                Ok(_) => {}
+                Err(Error::InvalidSlot(_)) => {
+                    let nearest = week_plan.nearest_slot(time);
+                    self.report(
+                        &path,
+                        shift(key_location, &key, time_offset),
+                        Some(&Value::from(time.to_string())),
+                        "time is not the start of a slot",
+                        Some(format!("the nearest valid slot is {weekday} {nearest}")),
+                    );
+                }
+                Err(err) => {
+                    self.report(&path, key_location, Some(&key_value), err.to_string(), None);
+                }
+            }
+        }
+    }
+}
+
+/// Byte offset of `part`, a subslice of `whole`, within `whole`.
+fn offset_in(whole: &str, part: &str) -> usize {
+    part.as_ptr() as usize - whole.as_ptr() as usize
+}
+
+/// Appends an object key to a JSON path.
+fn child(path: &str, key: &str) -> String {
+    let is_identifier = !key.is_empty()
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !key.starts_with(|c: char| c.is_ascii_digit());
+
+    if is_identifier {
+        format!("{path}.{key}")
+    } else {
+        format!("{path}[{}]", Value::from(key))
+    }
+}
+
+/// Moves a location pointing at the opening quote of `text` to its
+/// `offset`-th byte. Columns count characters, not bytes.
+fn shift(location: Location, text: &str, offset: usize) -> Location {
+    Location {
+        line: location.line,
+        column: location.column + 1 + text[..offset].chars().count(),
+    }
+}
+
+/// The name or short name, in any language, closest to a misspelt weekday.
+fn closest_weekday(name: &str) -> Option<&'static str> {
+    let name = name.to_lowercase();
+    Weekday::ALL
+        .into_iter()
+        .flat_map(|weekday| {
+            Language::ALL
+                .into_iter()
+                .flat_map(move |language| [weekday.name(language), weekday.short_name(language)])
+        })
+        .map(|candidate| (edit_distance(&name, &candidate.to_lowercase()), candidate))
+        // Any three letters are three edits away from a short name
+        .filter(|&(distance, candidate)| distance <= 3 && distance < candidate.chars().count())
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, candidate)| candidate)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let current = row[j + 1];
+            row[j + 1] = if ca == cb {
+                previous
+            } else {
+                1 + previous.min(row[j]).min(row[j + 1])
+            };
+            previous = current;
+        }
+    }
+
+    row[b.len()]
+}
+
+/// Maps the JSON paths of a well-formed document to the locations of their
+/// keys and values.
+#[derive(Default)]
+struct Locator {
+    keys: HashMap<String, Location>,
+    values: HashMap<String, Location>,
+}
+
+impl Locator {
+    fn new(source: &str) -> Self {
+        let mut scanner = Scanner {
+            source,
+            pos: 0,
+            location: Location { line: 1, column: 1 },
+            locator: Locator {
+                keys: HashMap::new(),
+                values: HashMap::new(),
+            },
+        };
+        scanner.value("$".to_owned());
+        scanner.locator
+    }
+
+    fn key(&self, path: &str) -> Location {
+        self.keys
+            .get(path)
+            .copied()
+            .unwrap_or_else(|| self.value(path))
+    }
+
+    fn value(&self, path: &str) -> Location {
+        self.values
+            .get(path)
+            .copied()
+            .unwrap_or(Location { line: 1, column: 1 })
+    }
+}
+
+/// A lax JSON walker. It is only run on documents `serde_json` accepted, so
+/// it doesn't need to report syntax errors.
+struct Scanner<'a> {
+    source: &'a str,
+    pos: usize,
+    location: Location,
+    locator: Locator,
+}
+
+impl Scanner<'_> {
+    fn peek(&self) -> Option<char> {
+        self.source[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        if c == '\n' {
+            self.location.line += 1;
+            self.location.column = 1;
+        } else {
+            self.location.column += 1;
+        }
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.bump();
+        }
+    }
+
+    fn string(&mut self) -> String {
+        let start = self.pos;
+        self.bump();
+        while let Some(c) = self.bump() {
+            match c {
+                '\\' => {
+                    self.bump();
+                }
+                '"' => break,
+                _ => {}
+            }
+        }
+
+        serde_json::from_str(&self.source[start..self.pos]).unwrap_or_default()
+    }
+
+    fn value(&mut self, path: String) {
+        self.skip_whitespace();
+        self.locator.values.insert(path.clone(), self.location);
+
+        match self.peek() {
+            Some('{') => {
+                self.bump();
+                loop {
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some('"') => {
+                            let location = self.location;
+                            let key = self.string();
+                            let path = child(&path, &key);
+                            self.locator.keys.insert(path.clone(), location);
+
+                            self.skip_whitespace();
+                            self.bump(); // ':'
+                            self.value(path);
+                        }
+                        Some(',') => {
+                            self.bump();
+                        }
+                        _ => {
+                            self.bump(); // '}'
+                            break;
+                        }
+                    }
+                }
+            }
+            Some('[') => {
+                self.bump();
+                let mut index = 0;
+                loop {
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some(']') | None => {
+                            self.bump();
+                            break;
+                        }
+                        Some(',') => {
+                            self.bump();
+                        }
+                        Some(_) => {
+                            self.value(format!("{path}[{index}]"));
+                            index += 1;
+                        }
+                    }
+                }
+            }
+            Some('"') => {
+                self.string();
+            }
+            _ => {
+                while self
+                    .peek()
+                    .is_some_and(|c| !c.is_whitespace() && !matches!(c, ',' | '}' | ']'))
+                {
+                    self.bump();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn valid_document() {
+        let source = r#"{
+            "version": 2,
+            "plan": { "Monday 08:30": "AAA" },
+            "start": "08:30",
+            "slot_duration": 90,
+            "slots": 7
+        }"#;
+
+        let week_plan = validate(source).unwrap();
+        assert!(week_plan.is_valid_slot(Time::new(10, 0).unwrap()));
+    }
+
+    #[test]
+    fn collects_every_problem() {
+        let source = r#"{
+  "plan": {
+    "Monday 14:00": "AAA",
+    "Tuseday 08:30": "BBB",
+    "Friday 8:75": "CCC"
+  },
+  "start": "08:30",
+  "slot_duration": 90,
+  "slots": 7,
+  "color": "red"
+}"#;
+
+        let report = validate(source).unwrap_err();
+        let problems = report.problems();
+        assert_eq!(problems.len(), 4);
+
+        assert_eq!(problems[0].path, r#"$.plan["Monday 14:00"]"#);
+        assert_eq!(
+            problems[0].location,
+            Location {
+                line: 3,
+                column: 13
+            }
+        );
+        assert_eq!(
+            problems[0].suggestion.as_deref(),
+            Some("the nearest valid slot is Monday 14:30")
+        );
+
+        assert_eq!(problems[1].path, r#"$.plan["Tuseday 08:30"]"#);
+        assert_eq!(problems[1].location, Location { line: 4, column: 6 });
+        assert_eq!(
+            problems[1].suggestion.as_deref(),
+            Some("did you mean `Tuesday`?")
+        );
+
+        assert_eq!(problems[2].path, r#"$.plan["Friday 8:75"]"#);
+        assert_eq!(
+            problems[2].location,
+            Location {
+                line: 5,
+                column: 15
+            }
+        );
+        assert_eq!(problems[2].value.as_deref(), Some(r#""8:75""#));
+
+        assert_eq!(problems[3].path, "$.color");
+        assert_eq!(
+            problems[3].location,
+            Location {
+                line: 10,
+                column: 3
+            }
+        );
+    }
+
+    #[test]
+    fn suggests_weekdays_in_every_language() {
+        assert_eq!(closest_weekday("Tuseday"), Some("Tuesday"));
+        assert_eq!(closest_weekday("mercledì"), Some("Mercoledì"));
+        assert_eq!(closest_weekday("Venrdi"), Some("Venerdì"));
+        assert_eq!(closest_weekday("Thr"), Some("Thu"));
+        assert_eq!(closest_weekday("xyz"), None);
+    }
+
+    #[test]
+    fn counts_columns_in_characters() {
+        let source = r#"{"plan": {"Lunedì 8:75": "A", "Mercoledì 09:00": "B"}, "start": "08:30", "slot_duration": 90, "slots": 7}"#;
+
+        let report = validate(source).unwrap_err();
+        let columns: Vec<_> = report
+            .problems()
+            .iter()
+            .map(|problem| problem.location.column)
+            .collect();
+        assert_eq!(columns, [21, 42]);
+    }
+
+    #[test]
+    fn reports_header_problems() {
+        let source = r#"{"plan": {}, "start": "20:00", "slot_duration": 90, "slots": 7}"#;
+        let report = validate(source).unwrap_err();
+
+        assert_eq!(report.problems().len(), 1);
+        assert_eq!(report.problems()[0].path, "$.slots");
+        assert_eq!(
+            report.problems()[0].suggestion.as_deref(),
+            Some("use at most 2 slots")
+        );
+
+        let source = r#"{"plan": {}, "slot_duration": 0, "slots": 7}"#;
+        let report = validate(source).unwrap_err();
+        let messages: Vec<_> = report.problems().iter().map(|p| &p.message).collect();
+
+        assert_eq!(report.problems().len(), 2);
+        assert!(messages.iter().any(|m| m.contains("missing field `start`")));
+        assert!(messages.iter().any(|m| m.contains("between 1 and")));
+
+        let source = r#"{"plan": {}, "start": "00:00", "slot_duration": 1441, "slots": 0}"#;
+        let report = validate(source).unwrap_err();
+
+        assert_eq!(report.problems().len(), 1);
+        assert_eq!(report.problems()[0].path, "$.slot_duration");
+        assert_eq!(
+            report.problems()[0].message,
+            "expected a number of minutes between 1 and 1440"
+        );
+    }
+
+    #[test]
+    fn reports_syntax_errors() {
+        let report = validate("{\n  \"plan\": {},\n}").unwrap_err();
+
+        assert_eq!(report.problems().len(), 1);
+        assert_eq!(report.problems()[0].location.line, 3);
+    }
+}
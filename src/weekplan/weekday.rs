@@ -1,8 +1,16 @@
+use std::fmt;
+use std::ops::RangeInclusive;
 use std::str::FromStr;
 
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize};
+use weekly_planner_core::weekday;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub use weekly_planner_core::weekday::ParseWeekdayError;
+
+use super::locale::Language;
+
+/// Days are ordered from Monday to Sunday, as in ISO-8601.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
 pub enum Weekday {
     Monday,
     Tuesday,
@@ -13,18 +21,200 @@ pub enum Weekday {
     Sunday,
 }
 
+impl Weekday {
+    /// Every day of the week, from Monday to Sunday.
+    pub const ALL: [Weekday; 7] = [
+        Weekday::Monday,
+        Weekday::Tuesday,
+        Weekday::Wednesday,
+        Weekday::Thursday,
+        Weekday::Friday,
+        Weekday::Saturday,
+        Weekday::Sunday,
+    ];
+
+    /// The following day, wrapping from Sunday to Monday.
+    pub fn next(self) -> Self {
+        Weekday::ALL[(self as usize + 1) % 7]
+    }
+
+    /// The previous day, wrapping from Monday to Sunday.
+    pub fn prev(self) -> Self {
+        Weekday::ALL[(self as usize + 6) % 7]
+    }
+
+    pub fn is_weekend(&self) -> bool {
+        matches!(self, Weekday::Saturday | Weekday::Sunday)
+    }
+
+    /// Iterates over the days of `range`, wrapping past Sunday if the range
+    /// starts after it ends: `Friday..=Monday` yields Friday, Saturday,
+    /// Sunday and Monday.
+    pub fn range(range: RangeInclusive<Weekday>) -> Days {
+        let (first, last) = range.into_inner();
+        Days {
+            next: Some(first),
+            last,
+        }
+    }
+
+    /// The seven days of a week starting on `first`.
+    pub fn week_starting(first: Weekday) -> Days {
+        Weekday::range(first..=first.prev())
+    }
+
+    /// The ISO-8601 number of the day, from 1 for Monday to 7 for Sunday.
+    pub fn iso_number(&self) -> u8 {
+        *self as u8 + 1
+    }
+
+    pub fn from_iso_number(number: u8) -> Option<Self> {
+        Weekday::ALL
+            .get(usize::from(number).checked_sub(1)?)
+            .copied()
+    }
+
+    pub fn name(&self, language: Language) -> &'static str {
+        let names = match language {
+            Language::English => weekday::ENGLISH,
+            Language::Italian => weekday::ITALIAN,
+        };
+
+        names[*self as usize]
+    }
+
+    pub fn short_name(&self, language: Language) -> &'static str {
+        let names = match language {
+            Language::English => ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"],
+            Language::Italian => ["Lun", "Mar", "Mer", "Gio", "Ven", "Sab", "Dom"],
+        };
+
+        names[*self as usize]
+    }
+}
+
+/// An iterator over consecutive days, see [`Weekday::range`].
+#[derive(Debug, Clone)]
+pub struct Days {
+    next: Option<Weekday>,
+    last: Weekday,
+}
+
+impl Iterator for Days {
+    type Item = Weekday;
+
+    fn next(&mut self) -> Option<Weekday> {
+        let current = self.next?;
+        self.next = (current != self.last).then(|| current.next());
+        Some(current)
+    }
+}
+
+/// Prints the English name of the day, see [`Weekday::name`] for others.
+impl fmt::Display for Weekday {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name(Language::English))
+    }
+}
+
+/// Accepts, ignoring case and accents:
+///
+/// - full names in any built-in language (`Monday`, `lunedì`, `Lunedi`)
+/// - prefixes of at least three letters that identify a single day
+///   (`Mon`, `Tues`, `Thurs`, `Mer`)
+/// - ISO-8601 numbers, from `1` for Monday to `7` for Sunday
 impl FromStr for Weekday {
-    type Err = ();
+    type Err = ParseWeekdayError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "Monday" => Ok(Weekday::Monday),
-            "Tuesday" => Ok(Weekday::Tuesday),
-            "Wednesday" => Ok(Weekday::Wednesday),
-            "Thursday" => Ok(Weekday::Thursday),
-            "Friday" => Ok(Weekday::Friday),
-            "Saturday" => Ok(Weekday::Saturday),
-            "Sunday" => Ok(Weekday::Sunday),
-            _ => Err(()),
+        weekday::parse(s).map(|days| Weekday::ALL[days])
+    }
+}
+
+impl<'de> Deserialize<'de> for Weekday {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        Weekday::from_str(&s).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let cases = [
+            ("Monday", Weekday::Monday),
+            ("monday", Weekday::Monday),
+            ("MON", Weekday::Monday),
+            ("Tues", Weekday::Tuesday),
+            ("thurs", Weekday::Thursday),
+            ("Lunedì", Weekday::Monday),
+            ("martedi", Weekday::Tuesday),
+            ("Mer", Weekday::Wednesday),
+            ("domenica", Weekday::Sunday),
+            ("Sab", Weekday::Saturday),
+            ("5", Weekday::Friday),
+            (" 7 ", Weekday::Sunday),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(input.parse(), Ok(expected), "{input}");
+        }
+    }
+
+    #[test]
+    fn parse_error() {
+        for input in ["", "Mo", "0", "8", "Someday", "Mondays"] {
+            let err = input.parse::<Weekday>().unwrap_err();
+            assert_eq!(err.input(), input);
+        }
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(Weekday::Monday.next(), Weekday::Tuesday);
+        assert_eq!(Weekday::Sunday.next(), Weekday::Monday);
+        assert_eq!(Weekday::Monday.prev(), Weekday::Sunday);
+        assert!(Weekday::Saturday.is_weekend());
+        assert!(!Weekday::Friday.is_weekend());
+        assert!(Weekday::Monday < Weekday::Sunday);
+    }
+
+    #[test]
+    fn ranges() {
+        let workdays: Vec<_> = Weekday::range(Weekday::Monday..=Weekday::Friday).collect();
+        assert_eq!(workdays, &Weekday::ALL[..5]);
+
+        let wrapping: Vec<_> = Weekday::range(Weekday::Saturday..=Weekday::Monday).collect();
+        assert_eq!(
+            wrapping,
+            [Weekday::Saturday, Weekday::Sunday, Weekday::Monday]
+        );
+
+        let single: Vec<_> = Weekday::range(Weekday::Friday..=Weekday::Friday).collect();
+        assert_eq!(single, [Weekday::Friday]);
+
+        let week: Vec<_> = Weekday::week_starting(Weekday::Sunday).collect();
+        assert_eq!(week.len(), 7);
+        assert_eq!(week[0], Weekday::Sunday);
+        assert_eq!(week[6], Weekday::Saturday);
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(Weekday::Thursday.to_string(), "Thursday");
+        assert_eq!(Weekday::Thursday.name(Language::Italian), "Giovedì");
+        for language in Language::ALL {
+            for weekday in Weekday::ALL {
+                assert_eq!(weekday.name(language).parse(), Ok(weekday));
+            }
         }
+        assert_eq!(Weekday::Thursday.short_name(Language::English), "Thu");
+        assert_eq!(Weekday::Sunday.iso_number(), 7);
+        assert_eq!(Weekday::from_iso_number(1), Some(Weekday::Monday));
     }
 }